@@ -0,0 +1,88 @@
+// -- future-based wrapper for tokio applications
+//
+// serial I/O stays blocking underneath: each call runs on tokio's blocking
+// pool, so a long read timeout never stalls the async worker threads.
+//
+// dropping a future (a `select!` that picked another branch, a
+// `tokio::time::timeout`) does not cancel the blocking call behind it: the
+// read runs on until data or its own timeout, and whatever it received is
+// lost. bound waits with the config timeout rather than by dropping futures.
+//
+//     let serial = AsyncSerial::connect("/dev/ttyUSB0", &SerialConfig::new(9600)).await?;
+//     serial.write(b"AT\r\n").await?;
+//     let reply = serial.read_line().await?;
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::{Serial, SerialConfig};
+
+/// `Serial` with async read/write, enabled by the `async` feature
+#[derive(Clone)]
+pub struct AsyncSerial {
+    inner: Serial,
+}
+
+impl AsyncSerial {
+    /// open `port` with `config` without blocking the runtime
+    pub async fn connect(port: &str, config: &SerialConfig) -> Result<Self> {
+        let port = port.to_string();
+        let config = config.clone();
+        let serial = blocking(move || Serial::with_config(&port, &config)).await?;
+        Ok(Self::from_serial(serial))
+    }
+
+    /// wrap an already open connection
+    pub fn from_serial(serial: Serial) -> Self {
+        Self { inner: serial }
+    }
+
+    /// the underlying blocking connection
+    pub fn serial(&self) -> &Serial {
+        &self.inner
+    }
+
+    /// see `Serial::read`
+    ///
+    /// if this future is dropped before it completes, the read still runs
+    /// on the blocking pool and the bytes it gets are discarded
+    pub async fn read(&self, buffer: &mut [u8]) -> Result<usize> {
+        let serial = self.inner.clone();
+        let mut chunk = vec![0u8; buffer.len()];
+        let (bytes_read, chunk) = blocking(move || {
+            let bytes_read = serial.read(&mut chunk)?;
+            Ok((bytes_read, chunk))
+        })
+        .await?;
+        buffer[..bytes_read].copy_from_slice(&chunk[..bytes_read]);
+        Ok(bytes_read)
+    }
+
+    /// see `Serial::write`
+    pub async fn write(&self, data: &[u8]) -> Result<usize> {
+        let serial = self.inner.clone();
+        let data = data.to_vec();
+        blocking(move || serial.write(&data)).await
+    }
+
+    /// see `Serial::read_line`; like `read`, dropping the future loses the line
+    pub async fn read_line(&self) -> Result<String> {
+        let serial = self.inner.clone();
+        blocking(move || serial.read_line()).await
+    }
+
+    /// see `Serial::flush`
+    pub async fn flush(&self) -> Result<()> {
+        let serial = self.inner.clone();
+        blocking(move || serial.flush()).await
+    }
+}
+
+/// run `op` on the blocking pool
+async fn blocking<T, F>(op: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(op)
+        .await
+        .unwrap_or_else(|_| Err(BitcoreError::ThreadPanicked("blocking task".into())))
+}
@@ -0,0 +1,36 @@
+// -- hayes AT commands
+//
+// a command is `AT...` plus CRLF. the modem may echo it, then sends any
+// number of information lines and ends with one final result code: `OK`,
+// `ERROR`, a `+CME ERROR: n` / `+CMS ERROR: n` from GSM modules, or one of
+// the dial results. the exchange itself is `Serial::at_command`.
+
+/// how a final result code ended a command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinalResult {
+    /// `OK`, or `CONNECT` (with any rate suffix) after a dial
+    Success,
+    /// any failure code; holds the line as received
+    Failure(String),
+}
+
+/// failure codes that end a command (besides the `+CME` / `+CMS` errors)
+const FAILURE_CODES: &[&str] = &["ERROR", "NO CARRIER", "BUSY", "NO DIALTONE", "NO ANSWER"];
+
+/// whether `line` is a final result code, and which kind
+///
+/// leading and trailing whitespace is ignored; anything else is an
+/// information line
+pub fn final_result(line: &str) -> Option<FinalResult> {
+    let line = line.trim();
+    if line == "OK" || line == "CONNECT" || line.starts_with("CONNECT ") {
+        return Some(FinalResult::Success);
+    }
+    if FAILURE_CODES.contains(&line)
+        || line.starts_with("+CME ERROR:")
+        || line.starts_with("+CMS ERROR:")
+    {
+        return Some(FinalResult::Failure(line.to_string()));
+    }
+    None
+}
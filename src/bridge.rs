@@ -0,0 +1,99 @@
+// -- software null-modem between two ports
+//
+// one thread per direction reads from one port in short slices and writes
+// everything to the other, until the caller's cancel flag is set.
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::{deadline_after, Serial};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// how long a bridge read holds a port, which bounds how late cancellation is seen
+const BRIDGE_READ_SLICE: Duration = Duration::from_millis(10);
+
+/// which way bridged bytes travelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BridgeDirection {
+    /// read from the first port, written to the second
+    AToB,
+    /// read from the second port, written to the first
+    BToA,
+}
+
+/// bytes forwarded by `Serial::bridge`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BridgeStats {
+    pub a_to_b: u64,
+    pub b_to_a: u64,
+}
+
+/// forward traffic both ways until `cancel` is set or a port fails
+pub(crate) fn run(
+    a: &Serial,
+    b: &Serial,
+    cancel: &AtomicBool,
+    tap: &(dyn Fn(BridgeDirection, &[u8]) + Sync),
+) -> Result<BridgeStats> {
+    // set when either direction fails, so the other one stops too
+    let failed = AtomicBool::new(false);
+    info!("bridge started");
+
+    let (a_to_b, b_to_a) = thread::scope(|scope| {
+        let a_to_b = scope.spawn(|| pump(a, b, BridgeDirection::AToB, cancel, &failed, tap));
+        let b_to_a = pump(b, a, BridgeDirection::BToA, cancel, &failed, tap);
+        let a_to_b = a_to_b
+            .join()
+            .unwrap_or_else(|_| Err(BitcoreError::ThreadPanicked("bridge thread".into())));
+        (a_to_b, b_to_a)
+    });
+
+    let stats = BridgeStats {
+        a_to_b: a_to_b?,
+        b_to_a: b_to_a?,
+    };
+    info!(
+        "bridge stopped: {} bytes a->b, {} bytes b->a",
+        stats.a_to_b, stats.b_to_a
+    );
+    Ok(stats)
+}
+
+/// copy `from` -> `to` and return the number of bytes forwarded
+fn pump(
+    from: &Serial,
+    to: &Serial,
+    direction: BridgeDirection,
+    cancel: &AtomicBool,
+    failed: &AtomicBool,
+    tap: &(dyn Fn(BridgeDirection, &[u8]) + Sync),
+) -> Result<u64> {
+    let mut forwarded = 0u64;
+    let mut buffer = [0u8; 256];
+
+    while !cancel.load(Ordering::Relaxed) && !failed.load(Ordering::Relaxed) {
+        let result = match from.read_for(&mut buffer, BRIDGE_READ_SLICE) {
+            Ok(0) | Err(BitcoreError::Timeout { .. }) => continue,
+            Ok(bytes_read) => {
+                let data = &buffer[..bytes_read];
+                tap(direction, data);
+                let deadline = deadline_after(to.settings().timeout);
+                to.write_all_deadline(data, deadline).map(|()| bytes_read)
+            }
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(bytes) => forwarded += bytes as u64,
+            Err(e) => {
+                warn!("bridge {:?} failed: {}", direction, e);
+                failed.store(true, Ordering::Relaxed);
+                return Err(e);
+            }
+        }
+    }
+
+    debug!("bridge {:?} forwarded {} bytes", direction, forwarded);
+    Ok(forwarded)
+}
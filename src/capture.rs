@@ -0,0 +1,119 @@
+// -- session capture for debugging
+//
+// every read and write is appended to a text file, one line per chunk:
+//
+//     <microseconds since open> <tx|rx> <hex bytes>
+//     000000001520 tx 50494e470a
+//     000000004310 rx 504f4e470a
+//
+// captures can be replayed in tests with `MockSerial::from_capture`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// direction of a captured chunk, seen from the host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// written by the host
+    Tx,
+    /// read by the host
+    Rx,
+}
+
+/// one captured chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureEntry {
+    /// time since the connection was opened
+    pub elapsed: Duration,
+    pub direction: Direction,
+    pub data: Vec<u8>,
+}
+
+/// appends captured traffic to a file
+pub(crate) struct CaptureWriter {
+    file: File,
+    started: Instant,
+}
+
+impl CaptureWriter {
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// second writer appending to the same file with the same time base
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            file: self.file.try_clone()?,
+            started: self.started,
+        })
+    }
+
+    pub(crate) fn record(&mut self, direction: Direction, data: &[u8]) {
+        let direction = match direction {
+            Direction::Tx => "tx",
+            Direction::Rx => "rx",
+        };
+        let hex: String = data.iter().map(|byte| format!("{byte:02x}")).collect();
+        let line = format!(
+            "{:012} {direction} {hex}\n",
+            self.started.elapsed().as_micros()
+        );
+
+        // capture is a debugging aid, never fail the actual io because of it
+        if let Err(e) = self.file.write_all(line.as_bytes()) {
+            tracing::warn!("failed to write capture: {}", e);
+        }
+    }
+}
+
+/// parse a capture file written by `SerialConfig::capture`
+pub fn read_capture<P: AsRef<Path>>(path: P) -> io::Result<Vec<CaptureEntry>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = parse_line(&line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed capture line {}: {line}", index + 1),
+            )
+        })?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn parse_line(line: &str) -> Option<CaptureEntry> {
+    let mut fields = line.split_whitespace();
+    let micros: u64 = fields.next()?.parse().ok()?;
+    let direction = match fields.next()? {
+        "tx" => Direction::Tx,
+        "rx" => Direction::Rx,
+        _ => return None,
+    };
+    let hex = fields.next().unwrap_or("");
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) || fields.next().is_some() {
+        return None;
+    }
+    let data = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    Some(CaptureEntry {
+        elapsed: Duration::from_micros(micros),
+        direction,
+        data,
+    })
+}
@@ -0,0 +1,87 @@
+// -- frame checksums
+//
+// table-driven; the tables are built at compile time.
+//
+//     crc16_modbus   poly 0x8005 reflected, init 0xFFFF  (modbus rtu)
+//     crc16_ccitt    poly 0x1021, init 0xFFFF            (CRC-16/CCITT-FALSE)
+//     crc8           poly 0x07, init 0x00                (CRC-8/SMBUS)
+
+const CRC16_MODBUS_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u16;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xa001
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+const CRC16_CCITT_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+const CRC8_TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-16 as used by modbus rtu; sent low byte first
+pub fn crc16_modbus(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (crc >> 8) ^ CRC16_MODBUS_TABLE[((crc ^ u16::from(byte)) & 0xff) as usize]
+    })
+}
+
+/// CRC-16/CCITT-FALSE (xmodem-style polynomial, initial value 0xFFFF)
+pub fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (crc << 8) ^ CRC16_CCITT_TABLE[((crc >> 8) ^ u16::from(byte)) as usize]
+    })
+}
+
+/// CRC-8 with polynomial 0x07 (SMBus packet error code)
+pub fn crc8(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0, |crc, &byte| CRC8_TABLE[(crc ^ byte) as usize])
+}
@@ -0,0 +1,61 @@
+// -- typed request/response protocols on top of Serial
+//
+// a Codec turns commands into bytes and response frames back into values;
+// Serial::query does the exchange in between (one locked transaction, shared
+// timeout, input cleared first). responses are framed by a delimiter byte.
+//
+//     enum Cmd { Temperature, SetLed(bool) }
+//     enum Resp { Celsius(f32), Ok }
+//
+//     struct Thermo;
+//
+//     impl Codec for Thermo {
+//         type Cmd = Cmd;
+//         type Resp = Resp;
+//
+//         fn encode(&self, cmd: &Cmd) -> Vec<u8> {
+//             match cmd {
+//                 Cmd::Temperature => b"T?\n".to_vec(),
+//                 Cmd::SetLed(on) => format!("L{}\n", u8::from(*on)).into_bytes(),
+//             }
+//         }
+//
+//         fn decode(&self, frame: &[u8]) -> Result<Resp> {
+//             match frame {
+//                 b"OK" => Ok(Resp::Ok),
+//                 [b'T', value @ ..] => std::str::from_utf8(value)
+//                     .ok()
+//                     .and_then(|value| value.parse().ok())
+//                     .map(Resp::Celsius)
+//                     .ok_or_else(|| BitcoreError::Deserialize {
+//                         reason: "bad temperature".to_string(),
+//                     }),
+//                 _ => Err(BitcoreError::Deserialize {
+//                     reason: "unknown reply".to_string(),
+//                 }),
+//             }
+//         }
+//     }
+//
+//     let reply = serial.query(&Thermo, &Cmd::Temperature)?;
+
+use crate::error::Result;
+
+/// encoding of commands and decoding of their responses for `Serial::query`
+pub trait Codec {
+    /// what the caller sends
+    type Cmd;
+    /// what comes back
+    type Resp;
+
+    /// bytes to write for `cmd`, including any framing the device expects
+    fn encode(&self, cmd: &Self::Cmd) -> Vec<u8>;
+
+    /// parse one response frame, without its delimiter
+    fn decode(&self, frame: &[u8]) -> Result<Self::Resp>;
+
+    /// byte that ends every response frame (default `\n`)
+    fn delimiter(&self) -> u8 {
+        b'\n'
+    }
+}
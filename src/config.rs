@@ -1,16 +1,31 @@
 // -- configuration for bitcore operations
 
+use crate::error::{BitcoreError, Result};
 use core::time::Duration;
+use std::time::Instant;
+use tracing::warn;
 
 /// retry configuration for operations
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct RetryConfig {
     /// maximum number of retry attempts
     pub max_attempts: usize,
     /// delay between retry attempts
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "retry_delay_ms", with = "crate::serde_config::millis")
+    )]
     pub retry_delay: Duration,
     /// exponential backoff multiplier (1.0 = no backoff)
     pub backoff_multiplier: f32,
+    /// which errors reads and writes retry; others fail at once
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub should_retry: fn(&BitcoreError) -> bool,
 }
 
 impl Default for RetryConfig {
@@ -19,6 +34,7 @@ impl Default for RetryConfig {
             max_attempts: 3,
             retry_delay: Duration::from_millis(100),
             backoff_multiplier: 1.5,
+            should_retry: BitcoreError::is_transient,
         }
     }
 }
@@ -44,6 +60,12 @@ impl RetryConfig {
         self
     }
 
+    /// only retry errors `should_retry` accepts (default `is_transient`)
+    pub fn with_should_retry(mut self, should_retry: fn(&BitcoreError) -> bool) -> Self {
+        self.should_retry = should_retry;
+        self
+    }
+
     /// calculate delay for given attempt number
     #[allow(
         clippy::cast_possible_truncation,
@@ -59,4 +81,216 @@ impl RetryConfig {
             Duration::from_nanos((self.retry_delay.as_nanos() as f32 * multiplier) as u64)
         }
     }
+
+    /// run `op` until it succeeds, fails with an error `should_retry` rejects,
+    /// or `max_attempts` retries have been used up
+    ///
+    /// sleeps `delay_for_attempt(n)` before retry `n`; returns the last error
+    pub fn retry<T>(
+        &self,
+        should_retry: impl Fn(&BitcoreError) -> bool,
+        mut op: impl FnMut() -> Result<T>,
+    ) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    if !self.wait_before_retry(&should_retry, attempt, &e, None) {
+                        return Err(e);
+                    }
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// decide whether `error` from attempt `attempt` (counted from 0) gets
+    /// another try, and if so log it and sleep the backoff
+    ///
+    /// with a `deadline`, the sleep is cut short at it and nothing is retried
+    /// once it has passed, so retries never stretch an operation past its
+    /// timeout
+    pub(crate) fn wait_before_retry(
+        &self,
+        should_retry: impl Fn(&BitcoreError) -> bool,
+        attempt: usize,
+        error: &BitcoreError,
+        deadline: Option<Instant>,
+    ) -> bool {
+        if attempt >= self.max_attempts || !should_retry(error) {
+            return false;
+        }
+        let mut delay = self.delay_for_attempt(attempt);
+        if let Some(deadline) = deadline {
+            match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => delay = delay.min(remaining),
+                _ => return false,
+            }
+        }
+        warn!(
+            "attempt {} failed: {}, retrying in {:?}",
+            attempt + 1,
+            error,
+            delay
+        );
+        std::thread::sleep(delay);
+        true
+    }
+}
+
+/// how bytes are turned into strings by the text-returning read methods
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TextDecoding {
+    /// reject invalid utf-8 with `BitcoreError::InvalidUtf8`
+    Strict,
+    /// replace invalid utf-8 sequences with U+FFFD
+    Lossy,
+    /// interpret every byte as a unicode code point (iso-8859-1)
+    #[default]
+    Latin1,
+}
+
+impl TextDecoding {
+    /// decode raw bytes according to this policy
+    pub fn decode(&self, bytes: Vec<u8>) -> Result<String> {
+        match self {
+            TextDecoding::Strict => {
+                String::from_utf8(bytes).map_err(|e| BitcoreError::InvalidUtf8 {
+                    bytes: e.into_bytes(),
+                })
+            }
+            TextDecoding::Lossy => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+            TextDecoding::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+        }
+    }
+}
+
+/// terminator `Serial::write_line` appends to each line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`, what AT-command modems and most terminals expect
+    #[default]
+    CrLf,
+    /// `\r`
+    Cr,
+    /// nothing; the caller supplies its own terminator
+    None,
+}
+
+impl LineEnding {
+    /// the terminator bytes
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Cr => b"\r",
+            LineEnding::None => b"",
+        }
+    }
+}
+
+/// rs-485 half-duplex: drive RTS as the transmitter enable around writes
+///
+/// with `enabled` set, every write asserts RTS, waits `delay_before_send`,
+/// sends, waits for the output buffer to drain, waits `delay_after_send` and
+/// releases RTS again so the transceiver goes back to receiving
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct Rs485Config {
+    pub enabled: bool,
+    /// RTS level that enables the transmitter (most adapters: high)
+    pub rts_active_high: bool,
+    /// transceiver settling time between asserting RTS and the first byte
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "delay_before_send_ms", with = "crate::serde_config::millis")
+    )]
+    pub delay_before_send: Duration,
+    /// hold time between the last byte leaving the uart and releasing RTS
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "delay_after_send_ms", with = "crate::serde_config::millis")
+    )]
+    pub delay_after_send: Duration,
+}
+
+impl Default for Rs485Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rts_active_high: true,
+            delay_before_send: Duration::ZERO,
+            delay_after_send: Duration::ZERO,
+        }
+    }
+}
+
+impl Rs485Config {
+    /// rs-485 mode on, RTS high while transmitting, no extra delays
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// set which RTS level enables the transmitter
+    pub fn rts_active_high(mut self, active_high: bool) -> Self {
+        self.rts_active_high = active_high;
+        self
+    }
+
+    /// wait this long after asserting RTS before sending
+    pub fn delay_before_send(mut self, delay: Duration) -> Self {
+        self.delay_before_send = delay;
+        self
+    }
+
+    /// wait this long after the output has drained before releasing RTS
+    pub fn delay_after_send(mut self, delay: Duration) -> Self {
+        self.delay_after_send = delay;
+        self
+    }
+}
+
+/// standard baud rates for the current platform
+///
+/// these are the termios speed constants on linux and other unix systems and
+/// the `CBR_*` rates on windows. drivers may accept more (or fewer) rates; this
+/// is a best-effort list, not a query of the device.
+pub fn standard_baud_rates() -> &'static [u32] {
+    #[cfg(target_os = "linux")]
+    const RATES: &[u32] = &[
+        50, 75, 110, 134, 150, 200, 300, 600, 1200, 1800, 2400, 4800, 9600, 19200, 38400, 57600,
+        115200, 230400, 460800, 500000, 576000, 921600, 1000000, 1152000, 1500000, 2000000,
+        2500000, 3000000, 3500000, 4000000,
+    ];
+    #[cfg(windows)]
+    const RATES: &[u32] = &[
+        110, 300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 38400, 57600, 115200, 128000, 256000,
+    ];
+    #[cfg(not(any(target_os = "linux", windows)))]
+    const RATES: &[u32] = &[
+        50, 75, 110, 134, 150, 200, 300, 600, 1200, 1800, 2400, 4800, 9600, 19200, 38400, 57600,
+        115200, 230400,
+    ];
+
+    RATES
 }
@@ -0,0 +1,181 @@
+// -- request/response multiplexing by correlation id
+//
+// every request and response is framed as
+//
+//     [id: u16 be] [payload length: u16 be] [payload]
+//
+// the peer echoes the request id in its response, so several requests can be
+// outstanding at once and answered in any order.
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::{deadline_after, Serial};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// size of the id + length header
+const HEADER_LEN: usize = 4;
+
+/// how long the reader holds the port per read, so writers are not starved
+const READ_SLICE: Duration = Duration::from_millis(10);
+
+type Pending = Arc<Mutex<HashMap<u16, Sender<Vec<u8>>>>>;
+
+/// frame a payload with its correlation id
+pub fn encode_frame(id: u16, payload: &[u8]) -> Result<Vec<u8>> {
+    let len = u16::try_from(payload.len()).map_err(|_| BitcoreError::InvalidParameter {
+        param: "payload".to_string(),
+        reason: format!(
+            "{} bytes exceeds the {} byte frame limit",
+            payload.len(),
+            u16::MAX
+        ),
+    })?;
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&id.to_be_bytes());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// parse one frame from the start of `buf`
+///
+/// returns the id, the payload and how many bytes were consumed, or `None` if
+/// the frame is not complete yet
+pub fn decode_frame(buf: &[u8]) -> Option<(u16, Vec<u8>, usize)> {
+    if buf.len() < HEADER_LEN {
+        return None;
+    }
+    let id = u16::from_be_bytes([buf[0], buf[1]]);
+    let len = u16::from_be_bytes([buf[2], buf[3]]) as usize;
+    let end = HEADER_LEN + len;
+    if buf.len() < end {
+        return None;
+    }
+    Some((id, buf[HEADER_LEN..end].to_vec(), end))
+}
+
+/// serial connection that matches responses to requests by id
+///
+/// a background thread reads incoming frames and hands each one to the caller
+/// waiting on its id; the thread stops when this is dropped
+pub struct CorrelatedSerial {
+    serial: Serial,
+    pending: Pending,
+    timeout: Duration,
+    stop: Arc<AtomicBool>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl CorrelatedSerial {
+    /// start routing responses on `serial`; each request waits at most `timeout`
+    pub fn new(serial: Serial, timeout: Duration) -> Self {
+        let pending: Pending = Arc::new(Mutex::new(HashMap::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let reader = {
+            let serial = serial.clone();
+            let pending = Arc::clone(&pending);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || read_loop(&serial, &pending, &stop))
+        };
+
+        Self {
+            serial,
+            pending,
+            timeout,
+            stop,
+            reader: Some(reader),
+        }
+    }
+
+    /// send `payload` tagged with `id` and wait for the response carrying the same id
+    pub fn request(&self, id: u16, payload: &[u8]) -> Result<Vec<u8>> {
+        let frame = encode_frame(id, payload)?;
+        let (sender, receiver) = mpsc::channel();
+
+        {
+            let mut pending = self
+                .pending
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+            if pending.contains_key(&id) {
+                return Err(BitcoreError::InvalidParameter {
+                    param: "id".to_string(),
+                    reason: format!("request {id} is already in flight"),
+                });
+            }
+            pending.insert(id, sender);
+        }
+
+        let deadline = deadline_after(self.timeout);
+        let result = self
+            .serial
+            .write_all_deadline(&frame, deadline)
+            .and_then(|()| {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match receiver.recv_timeout(remaining) {
+                    Ok(response) => Ok(response),
+                    Err(RecvTimeoutError::Timeout) => Err(BitcoreError::timeout(self.timeout)),
+                    Err(RecvTimeoutError::Disconnected) => Err(BitcoreError::NotConnected),
+                }
+            });
+
+        if let Ok(mut pending) = self.pending.lock() {
+            pending.remove(&id);
+        }
+        result
+    }
+
+    /// the underlying connection
+    pub fn serial(&self) -> &Serial {
+        &self.serial
+    }
+}
+
+impl Drop for CorrelatedSerial {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// read frames until stopped and route them to their waiting requests
+fn read_loop(serial: &Serial, pending: &Pending, stop: &AtomicBool) {
+    let mut received = Vec::new();
+    let mut buffer = [0u8; 256];
+
+    while !stop.load(Ordering::Relaxed) {
+        match serial.read_for(&mut buffer, READ_SLICE) {
+            Ok(bytes_read) => received.extend_from_slice(&buffer[..bytes_read]),
+            Err(BitcoreError::Timeout { .. }) => continue,
+            Err(BitcoreError::NotConnected) => {
+                debug!("correlated reader stopping: not connected");
+                return;
+            }
+            Err(e) => {
+                warn!("correlated reader error: {}", e);
+                thread::sleep(READ_SLICE);
+                continue;
+            }
+        }
+
+        while let Some((id, payload, consumed)) = decode_frame(&received) {
+            received.drain(..consumed);
+            let waiter = pending.lock().ok().and_then(|mut p| p.remove(&id));
+            match waiter {
+                Some(sender) => {
+                    let _ = sender.send(payload);
+                }
+                None => warn!("dropping response for unknown request id {}", id),
+            }
+        }
+    }
+}
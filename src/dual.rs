@@ -0,0 +1,91 @@
+// -- devices with separate data and control ports
+//
+// cellular modems (AT channel + PPP channel) and some instruments expose two
+// serial interfaces that only make sense together. DualSerial opens, closes
+// and reconnects them as a unit; I/O goes through the two `Serial`s directly.
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::{Serial, SerialConfig};
+use tracing::{info, warn};
+
+/// a data port and a control port managed together
+pub struct DualSerial {
+    pub data: Serial,
+    pub control: Serial,
+    data_port: String,
+    control_port: String,
+}
+
+impl DualSerial {
+    /// open both ports; if the control port fails the data port is closed again
+    pub fn open(
+        data_port: &str,
+        data_config: &SerialConfig,
+        control_port: &str,
+        control_config: &SerialConfig,
+    ) -> Result<Self> {
+        let data = Serial::with_config(data_port, data_config)?;
+        let control = match Serial::with_config(control_port, control_config) {
+            Ok(control) => control,
+            Err(e) => {
+                if let Err(close) = data.disconnect() {
+                    warn!("failed to close data port {}: {}", data_port, close);
+                }
+                return Err(e);
+            }
+        };
+
+        info!("opened dual port {} + {}", data_port, control_port);
+        Ok(Self {
+            data,
+            control,
+            data_port: data_port.to_string(),
+            control_port: control_port.to_string(),
+        })
+    }
+
+    /// close both ports
+    ///
+    /// both are always attempted; the first failure is returned. a port that
+    /// is already closed is not an error.
+    pub fn close(&self) -> Result<()> {
+        let data = ignore_not_connected(self.data.disconnect());
+        let control = ignore_not_connected(self.control.disconnect());
+        data.and(control)
+    }
+
+    /// close both ports and open them again with their original configs
+    ///
+    /// if either fails to reopen, both are left closed
+    pub fn reconnect(&self) -> Result<()> {
+        self.close()?;
+
+        let result = self
+            .data
+            .switch_port(&self.data_port)
+            .and_then(|()| self.control.switch_port(&self.control_port));
+        if let Err(e) = result {
+            warn!("dual reconnect failed: {}", e);
+            let _ = self.close();
+            return Err(e);
+        }
+
+        info!(
+            "reconnected dual port {} + {}",
+            self.data_port, self.control_port
+        );
+        Ok(())
+    }
+
+    /// true only when both ports are open
+    pub fn is_connected(&self) -> bool {
+        self.data.is_connected() && self.control.is_connected()
+    }
+}
+
+fn ignore_not_connected(result: Result<()>) -> Result<()> {
+    match result {
+        Err(BitcoreError::NotConnected) => Ok(()),
+        other => other,
+    }
+}
@@ -1,6 +1,7 @@
 // -- error handling for bitcore
 
 use core::fmt;
+use core::time::Duration;
 use std::io;
 
 /// custom error type for bitcore operations
@@ -21,6 +22,9 @@ pub enum BitcoreError {
     /// lock acquisition failed
     LockFailed(String),
 
+    /// a worker thread panicked; says which one
+    ThreadPanicked(String),
+
     /// operation timed out
     Timeout { timeout_ms: u64 },
 
@@ -29,6 +33,150 @@ pub enum BitcoreError {
 
     /// invalid parameter
     InvalidParameter { param: String, reason: String },
+
+    /// received bytes are not valid utf-8
+    InvalidUtf8 { bytes: Vec<u8> },
+
+    /// port is held by another process
+    PortBusy { port: String },
+
+    /// not allowed to open the port
+    PermissionDenied { port: String },
+
+    /// self-test pattern did not round-trip
+    SelfTestFailed { mismatched: usize, length: usize },
+
+    /// device went away while the port was open
+    Disconnected { port: String },
+
+    /// received data could not be parsed
+    Deserialize { reason: String },
+
+    /// timed out before the delimiter arrived; `partial` holds what was received
+    DelimiterNotFound { partial: Vec<u8>, timeout_ms: u64 },
+
+    /// timed out after filling only the first `got` of `expected` bytes
+    PartialRead { got: usize, expected: usize },
+
+    /// a frame's trailing checksum did not match its contents
+    ChecksumMismatch { expected: u16, actual: u16 },
+
+    /// a modbus slave rejected the request with an exception response
+    ModbusException { function: u8, code: u8 },
+
+    /// more than `max_len` bytes arrived without a line ending
+    LineTooLong { max_len: usize },
+
+    /// a modem answered an AT command with `ERROR` or another failure code
+    AtCommandFailed { command: String, result: String },
+
+    /// the modem dropped DCD: the line to the far end is gone
+    CarrierLost { port: String },
+
+    /// the device on `port` did not answer a probe with the expected reply
+    ProbeMismatch { port: String },
+
+    /// none of the candidate ports could be used; holds each port and why it failed
+    NoPortAvailable {
+        attempts: Vec<(String, BitcoreError)>,
+    },
+}
+
+impl BitcoreError {
+    /// map a failure to open `port` to the most specific variant
+    pub(crate) fn open_failed(port: &str, err: serialport::Error) -> Self {
+        match err.kind() {
+            serialport::ErrorKind::NoDevice => BitcoreError::PortBusy {
+                port: port.to_string(),
+            },
+            serialport::ErrorKind::Io(io::ErrorKind::PermissionDenied) => {
+                BitcoreError::PermissionDenied {
+                    port: port.to_string(),
+                }
+            }
+            _ => BitcoreError::SerialPort(err),
+        }
+    }
+
+    /// whether opening a port may succeed if tried again
+    ///
+    /// busy ports and ports that are not (yet) enumerated are transient;
+    /// permission and parameter problems are not
+    pub fn is_transient_open_error(&self) -> bool {
+        match self {
+            BitcoreError::PortBusy { .. } | BitcoreError::NotConnected => true,
+            BitcoreError::SerialPort(e) => {
+                e.kind() == serialport::ErrorKind::Io(io::ErrorKind::NotFound)
+            }
+            _ => false,
+        }
+    }
+
+    /// whether an operation that failed with this error may succeed if retried
+    ///
+    /// timeouts and passing io conditions are; a closed or vanished port,
+    /// bad parameters and protocol errors will fail the same way again.
+    /// the default `RetryConfig::should_retry`
+    pub fn is_transient(&self) -> bool {
+        let transient_kind = |kind: io::ErrorKind| {
+            matches!(
+                kind,
+                io::ErrorKind::Interrupted
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::TimedOut
+                    | io::ErrorKind::WriteZero
+                    // unclassified driver errors (serialport's `Unknown`)
+                    | io::ErrorKind::Other
+            )
+        };
+        match self {
+            BitcoreError::Timeout { .. } => true,
+            BitcoreError::Io(e) => !Self::is_disconnect(e) && transient_kind(e.kind()),
+            BitcoreError::SerialPort(e) => match e.kind() {
+                serialport::ErrorKind::Io(kind) => transient_kind(kind),
+                serialport::ErrorKind::Unknown => true,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// whether an io error means the device is gone rather than a passing glitch
+    pub(crate) fn is_disconnect(err: &io::Error) -> bool {
+        if matches!(
+            err.kind(),
+            io::ErrorKind::BrokenPipe
+                | io::ErrorKind::NotConnected
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        ) {
+            return true;
+        }
+
+        // unplugged usb adapters report EIO, ENXIO or ENODEV
+        #[cfg(unix)]
+        if matches!(err.raw_os_error(), Some(5 | 6 | 19)) {
+            return true;
+        }
+
+        false
+    }
+
+    /// whether the port is unusable after this error and needs reconnecting
+    pub fn is_fatal(&self) -> bool {
+        match self {
+            BitcoreError::Disconnected { .. } => true,
+            BitcoreError::Io(e) => Self::is_disconnect(e),
+            _ => false,
+        }
+    }
+
+    /// build a `Timeout` error for the given budget
+    pub(crate) fn timeout(timeout: Duration) -> Self {
+        BitcoreError::Timeout {
+            timeout_ms: timeout.as_millis().min(u64::MAX as u128) as u64,
+        }
+    }
 }
 
 impl fmt::Display for BitcoreError {
@@ -39,6 +187,7 @@ impl fmt::Display for BitcoreError {
             BitcoreError::NotConnected => write!(f, "connection not established"),
             BitcoreError::AlreadyConnected => write!(f, "connection already exists"),
             BitcoreError::LockFailed(msg) => write!(f, "lock acquisition failed: {msg}"),
+            BitcoreError::ThreadPanicked(thread) => write!(f, "{thread} panicked"),
             BitcoreError::Timeout { timeout_ms } => {
                 write!(f, "operation timed out after {timeout_ms}ms")
             }
@@ -48,11 +197,153 @@ impl fmt::Display for BitcoreError {
             BitcoreError::InvalidParameter { param, reason } => {
                 write!(f, "invalid parameter {param}: {reason}")
             }
+            BitcoreError::InvalidUtf8 { bytes } => {
+                write!(f, "invalid utf-8 in {} received bytes", bytes.len())
+            }
+            BitcoreError::PortBusy { port } => write!(f, "port {port} is busy"),
+            BitcoreError::PermissionDenied { port } => {
+                write!(f, "permission denied opening port {port}")
+            }
+            BitcoreError::SelfTestFailed { mismatched, length } => {
+                write!(
+                    f,
+                    "self-test failed: {mismatched} of {length} bytes mismatched"
+                )
+            }
+            BitcoreError::Disconnected { port } => write!(f, "port {port} disconnected"),
+            BitcoreError::Deserialize { reason } => write!(f, "failed to parse response: {reason}"),
+            BitcoreError::DelimiterNotFound {
+                partial,
+                timeout_ms,
+            } => write!(
+                f,
+                "delimiter not received within {timeout_ms}ms ({} bytes read)",
+                partial.len()
+            ),
+            BitcoreError::PartialRead { got, expected } => {
+                write!(f, "timed out after reading {got} of {expected} bytes")
+            }
+            BitcoreError::ChecksumMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "checksum mismatch: frame carries {expected:#06x}, computed {actual:#06x}"
+                )
+            }
+            BitcoreError::ModbusException { function, code } => write!(
+                f,
+                "modbus exception {code:#04x} ({}) for function {function:#04x}",
+                crate::modbus::exception_name(*code)
+            ),
+            BitcoreError::LineTooLong { max_len } => {
+                write!(f, "line longer than {max_len} bytes")
+            }
+            BitcoreError::AtCommandFailed { command, result } => {
+                write!(f, "AT command {command} failed: {result}")
+            }
+            BitcoreError::CarrierLost { port } => write!(f, "carrier lost on port {port}"),
+            BitcoreError::ProbeMismatch { port } => {
+                write!(f, "no matching reply to probe on port {port}")
+            }
+            BitcoreError::NoPortAvailable { attempts } if attempts.is_empty() => {
+                write!(f, "no port available: no candidate ports")
+            }
+            BitcoreError::NoPortAvailable { attempts } => {
+                write!(f, "no port available (tried ")?;
+                for (i, (port, err)) in attempts.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{port}: {err}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BitcoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BitcoreError::SerialPort(e) => Some(e),
+            BitcoreError::Io(e) => Some(e),
+            _ => None,
         }
     }
 }
 
-impl std::error::Error for BitcoreError {}
+// io::Error is not Clone; a copy keeps its kind and message
+impl Clone for BitcoreError {
+    fn clone(&self) -> Self {
+        match self {
+            BitcoreError::SerialPort(e) => BitcoreError::SerialPort(e.clone()),
+            BitcoreError::Io(e) => BitcoreError::Io(io::Error::new(e.kind(), e.to_string())),
+            BitcoreError::NotConnected => BitcoreError::NotConnected,
+            BitcoreError::AlreadyConnected => BitcoreError::AlreadyConnected,
+            BitcoreError::LockFailed(msg) => BitcoreError::LockFailed(msg.clone()),
+            BitcoreError::ThreadPanicked(thread) => BitcoreError::ThreadPanicked(thread.clone()),
+            BitcoreError::Timeout { timeout_ms } => BitcoreError::Timeout {
+                timeout_ms: *timeout_ms,
+            },
+            BitcoreError::RetryLimitExceeded { attempts } => BitcoreError::RetryLimitExceeded {
+                attempts: *attempts,
+            },
+            BitcoreError::InvalidParameter { param, reason } => BitcoreError::InvalidParameter {
+                param: param.clone(),
+                reason: reason.clone(),
+            },
+            BitcoreError::InvalidUtf8 { bytes } => BitcoreError::InvalidUtf8 {
+                bytes: bytes.clone(),
+            },
+            BitcoreError::PortBusy { port } => BitcoreError::PortBusy { port: port.clone() },
+            BitcoreError::PermissionDenied { port } => {
+                BitcoreError::PermissionDenied { port: port.clone() }
+            }
+            BitcoreError::SelfTestFailed { mismatched, length } => BitcoreError::SelfTestFailed {
+                mismatched: *mismatched,
+                length: *length,
+            },
+            BitcoreError::Disconnected { port } => {
+                BitcoreError::Disconnected { port: port.clone() }
+            }
+            BitcoreError::Deserialize { reason } => BitcoreError::Deserialize {
+                reason: reason.clone(),
+            },
+            BitcoreError::DelimiterNotFound {
+                partial,
+                timeout_ms,
+            } => BitcoreError::DelimiterNotFound {
+                partial: partial.clone(),
+                timeout_ms: *timeout_ms,
+            },
+            BitcoreError::PartialRead { got, expected } => BitcoreError::PartialRead {
+                got: *got,
+                expected: *expected,
+            },
+            BitcoreError::ChecksumMismatch { expected, actual } => BitcoreError::ChecksumMismatch {
+                expected: *expected,
+                actual: *actual,
+            },
+            BitcoreError::ModbusException { function, code } => BitcoreError::ModbusException {
+                function: *function,
+                code: *code,
+            },
+            BitcoreError::LineTooLong { max_len } => {
+                BitcoreError::LineTooLong { max_len: *max_len }
+            }
+            BitcoreError::AtCommandFailed { command, result } => BitcoreError::AtCommandFailed {
+                command: command.clone(),
+                result: result.clone(),
+            },
+            BitcoreError::CarrierLost { port } => BitcoreError::CarrierLost { port: port.clone() },
+            BitcoreError::ProbeMismatch { port } => {
+                BitcoreError::ProbeMismatch { port: port.clone() }
+            }
+            BitcoreError::NoPortAvailable { attempts } => BitcoreError::NoPortAvailable {
+                attempts: attempts.clone(),
+            },
+        }
+    }
+}
 
 impl From<serialport::Error> for BitcoreError {
     fn from(err: serialport::Error) -> Self {
@@ -64,8 +355,9 @@ impl From<io::Error> for BitcoreError {
     fn from(err: io::Error) -> Self {
         match err.kind() {
             io::ErrorKind::NotConnected => BitcoreError::NotConnected,
-            io::ErrorKind::TimedOut => BitcoreError::Timeout { timeout_ms: 0 },
             io::ErrorKind::AlreadyExists => BitcoreError::AlreadyConnected,
+            // TimedOut stays Io() too: the error does not say how long was waited,
+            // so the port code builds Timeout itself with the real value.
             // Keep Io() for less common I/O errors like UnexpectedEof, WriteZero, etc.
             _ => BitcoreError::Io(err),
         }
@@ -77,7 +369,10 @@ impl From<BitcoreError> for io::Error {
         match err {
             BitcoreError::Io(io_err) => io_err,
             BitcoreError::NotConnected => io::Error::new(io::ErrorKind::NotConnected, err),
-            BitcoreError::Timeout { .. } => io::Error::new(io::ErrorKind::TimedOut, err),
+            BitcoreError::Timeout { .. }
+            | BitcoreError::DelimiterNotFound { .. }
+            | BitcoreError::PartialRead { .. } => io::Error::new(io::ErrorKind::TimedOut, err),
+            BitcoreError::Disconnected { .. } => io::Error::new(io::ErrorKind::BrokenPipe, err),
             _ => io::Error::other(err),
         }
     }
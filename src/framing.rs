@@ -0,0 +1,159 @@
+// -- binary packet framing
+//
+// COBS (consistent overhead byte stuffing) removes every zero byte from a
+// packet, so a single 0x00 can mark the end of each frame on the wire no
+// matter what the payload contains. `Serial::write_frame` / `read_frame`
+// handle the delimiter; the functions here only do the byte stuffing.
+//
+// SLIP lives in `slip`, used by `Serial::write_slip` / `read_slip`.
+//
+// `LengthPrefix` describes the other common scheme: a fixed-size length
+// header in front of each payload (`Serial::write_length_prefixed` /
+// `read_length_prefixed`).
+
+pub mod slip;
+
+use crate::error::{BitcoreError, Result};
+
+/// longest COBS encoding of a `len`-byte payload, without the delimiter
+pub fn cobs_max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 1
+}
+
+/// COBS-encode `data`; the result contains no zero bytes and no delimiter
+pub fn cobs_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(cobs_max_encoded_len(data.len()));
+    // position of the overhead byte for the block being built
+    let mut code_index = 0;
+    encoded.push(0);
+
+    for (i, &byte) in data.iter().enumerate() {
+        if byte != 0 {
+            encoded.push(byte);
+        }
+        let block_len = encoded.len() - code_index;
+        // a full block needs a new one only if more data follows
+        if byte == 0 || (block_len == 0xff && i + 1 < data.len()) {
+            encoded[code_index] = block_len as u8;
+            code_index = encoded.len();
+            encoded.push(0);
+        }
+    }
+
+    encoded[code_index] = (encoded.len() - code_index) as u8;
+    encoded
+}
+
+/// decode one COBS frame (without its zero delimiter)
+///
+/// fails with `InvalidParameter` if the frame contains a zero byte or an
+/// overhead byte points past the end of the frame
+pub fn cobs_decode(encoded: &[u8]) -> Result<Vec<u8>> {
+    if encoded.is_empty() {
+        return Err(invalid_frame("empty COBS frame".into()));
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len());
+    let mut index = 0;
+    while index < encoded.len() {
+        let code = encoded[index] as usize;
+        if code == 0 {
+            return Err(invalid_frame(format!("zero byte at offset {index}")));
+        }
+
+        let block_end = index + code;
+        if block_end > encoded.len() {
+            return Err(invalid_frame(format!(
+                "overhead byte {code:#04x} at offset {index} runs past the end of the frame"
+            )));
+        }
+
+        let block = &encoded[index + 1..block_end];
+        if let Some(offset) = block.iter().position(|&b| b == 0) {
+            return Err(invalid_frame(format!(
+                "zero byte at offset {}",
+                index + 1 + offset
+            )));
+        }
+        decoded.extend_from_slice(block);
+
+        index = block_end;
+        // a full block (0xff) carries no implicit zero, nor does the last one
+        if code < 0xff && index < encoded.len() {
+            decoded.push(0);
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// size and byte order of a length header in front of each payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthPrefix {
+    U16Be,
+    U16Le,
+    U32Be,
+    U32Le,
+}
+
+impl LengthPrefix {
+    /// bytes taken by the header itself
+    pub fn header_len(self) -> usize {
+        match self {
+            LengthPrefix::U16Be | LengthPrefix::U16Le => 2,
+            LengthPrefix::U32Be | LengthPrefix::U32Le => 4,
+        }
+    }
+
+    /// largest payload length the header can express
+    pub fn max_payload_len(self) -> usize {
+        match self {
+            LengthPrefix::U16Be | LengthPrefix::U16Le => u16::MAX as usize,
+            LengthPrefix::U32Be | LengthPrefix::U32Le => u32::MAX as usize,
+        }
+    }
+
+    /// header announcing a payload of `len` bytes
+    pub fn encode(self, len: usize) -> Result<Vec<u8>> {
+        if len > self.max_payload_len() {
+            return Err(invalid_frame(format!(
+                "{len} bytes do not fit a {}-byte length prefix",
+                self.header_len()
+            )));
+        }
+        Ok(match self {
+            LengthPrefix::U16Be => (len as u16).to_be_bytes().to_vec(),
+            LengthPrefix::U16Le => (len as u16).to_le_bytes().to_vec(),
+            LengthPrefix::U32Be => (len as u32).to_be_bytes().to_vec(),
+            LengthPrefix::U32Le => (len as u32).to_le_bytes().to_vec(),
+        })
+    }
+
+    /// payload length announced by `header`, which must be `header_len` bytes
+    pub fn decode(self, header: &[u8]) -> Result<usize> {
+        if header.len() != self.header_len() {
+            return Err(invalid_frame(format!(
+                "length prefix needs {} bytes, got {}",
+                self.header_len(),
+                header.len()
+            )));
+        }
+        Ok(match self {
+            LengthPrefix::U16Be => u16::from_be_bytes([header[0], header[1]]).into(),
+            LengthPrefix::U16Le => u16::from_le_bytes([header[0], header[1]]).into(),
+            LengthPrefix::U32Be => {
+                u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize
+            }
+            LengthPrefix::U32Le => {
+                u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize
+            }
+        })
+    }
+}
+
+pub(crate) fn invalid_frame(reason: String) -> BitcoreError {
+    BitcoreError::InvalidParameter {
+        param: "frame".to_string(),
+        reason,
+    }
+}
@@ -0,0 +1,67 @@
+// -- SLIP (RFC 1055) framing
+//
+// frames end with END (0xC0); END and ESC (0xDB) inside the payload are sent
+// as ESC ESC_END and ESC ESC_ESC.
+
+use super::invalid_frame;
+use crate::error::Result;
+
+pub const END: u8 = 0xc0;
+pub const ESC: u8 = 0xdb;
+pub const ESC_END: u8 = 0xdc;
+pub const ESC_ESC: u8 = 0xdd;
+
+/// SLIP-encode `data` into a complete frame, including the closing END
+pub fn slip_encode(data: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(data.len() + 2);
+    for &byte in data {
+        match byte {
+            END => encoded.extend_from_slice(&[ESC, ESC_END]),
+            ESC => encoded.extend_from_slice(&[ESC, ESC_ESC]),
+            byte => encoded.push(byte),
+        }
+    }
+    encoded.push(END);
+    encoded
+}
+
+/// decode one SLIP frame
+///
+/// END bytes at either end are ignored, so both `slip_encode` output and
+/// frames with a leading END decode. fails with `InvalidParameter` on an END
+/// inside the frame or an ESC not followed by ESC_END / ESC_ESC.
+pub fn slip_decode(frame: &[u8]) -> Result<Vec<u8>> {
+    let start = frame.iter().position(|&b| b != END).unwrap_or(frame.len());
+    let end = frame
+        .iter()
+        .rposition(|&b| b != END)
+        .map_or(start, |i| i + 1);
+    let body = &frame[start..end];
+
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut bytes = body.iter().enumerate();
+    while let Some((offset, &byte)) = bytes.next() {
+        match byte {
+            ESC => match bytes.next() {
+                Some((_, &ESC_END)) => decoded.push(END),
+                Some((_, &ESC_ESC)) => decoded.push(ESC),
+                Some((_, &other)) => {
+                    return Err(invalid_frame(format!(
+                        "invalid escape {other:#04x} at offset {}",
+                        start + offset + 1
+                    )))
+                }
+                None => return Err(invalid_frame("frame ends inside an escape".into())),
+            },
+            END => {
+                return Err(invalid_frame(format!(
+                    "END inside the frame at offset {}",
+                    start + offset
+                )))
+            }
+            byte => decoded.push(byte),
+        }
+    }
+
+    Ok(decoded)
+}
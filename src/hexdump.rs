@@ -0,0 +1,40 @@
+// -- hex + ascii dumps of raw traffic
+//
+// used by `SerialConfig::trace_bytes` to log every buffer at trace level:
+//
+//     0000  48 65 6c 6c 6f 0d 0a                             Hello..
+
+use std::fmt::Write;
+
+/// bytes shown per dump line
+const BYTES_PER_LINE: usize = 16;
+
+/// format `data` as offset, hex bytes and printable ascii, 16 bytes per line
+///
+/// bytes outside printable ascii show as `.` in the text column
+pub fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (index, line) in data.chunks(BYTES_PER_LINE).enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{:04x} ", index * BYTES_PER_LINE);
+        for slot in 0..BYTES_PER_LINE {
+            match line.get(slot) {
+                Some(byte) => {
+                    let _ = write!(out, " {byte:02x}");
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str("  ");
+        out.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                char::from(byte)
+            } else {
+                '.'
+            }
+        }));
+    }
+    out
+}
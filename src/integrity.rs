@@ -0,0 +1,79 @@
+// -- end-to-end data integrity patterns
+//
+// used by `Serial::integrity_test`: a pattern is written, read back through a
+// loopback or echoing peer, and compared byte by byte.
+
+/// test pattern written by `Serial::integrity_test`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatternKind {
+    /// 0, 1, 2, ... 255, 0, 1, ... (catches dropped or reordered bytes)
+    Incrementing,
+    /// pseudo-random bytes, reproducible from the seed
+    Random(u64),
+    /// 0x01, 0x02, 0x04 ... 0x80 repeated (catches stuck data bits)
+    Walking1s,
+    /// the same byte throughout
+    AllSame(u8),
+}
+
+impl PatternKind {
+    /// the first `length` bytes of this pattern
+    pub fn generate(&self, length: usize) -> Vec<u8> {
+        match *self {
+            PatternKind::Incrementing => (0..length).map(|i| i as u8).collect(),
+            PatternKind::Random(seed) => {
+                // xorshift64*; a zero state would only ever produce zeros
+                let mut state = seed.max(1);
+                (0..length)
+                    .map(|_| {
+                        state ^= state >> 12;
+                        state ^= state << 25;
+                        state ^= state >> 27;
+                        (state.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 56) as u8
+                    })
+                    .collect()
+            }
+            PatternKind::Walking1s => (0..length).map(|i| 1u8 << (i % 8)).collect(),
+            PatternKind::AllSame(byte) => vec![byte; length],
+        }
+    }
+}
+
+/// result of `Serial::integrity_test`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub pattern: PatternKind,
+    /// number of bytes written and read back
+    pub length: usize,
+    /// offsets of the bytes that came back different
+    pub mismatched_positions: Vec<usize>,
+}
+
+impl IntegrityReport {
+    /// compare `received` against the `sent` pattern
+    pub(crate) fn compare(pattern: PatternKind, sent: &[u8], received: &[u8]) -> Self {
+        let mismatched_positions = sent
+            .iter()
+            .zip(received)
+            .enumerate()
+            .filter(|(_, (sent, received))| sent != received)
+            .map(|(position, _)| position)
+            .collect();
+
+        Self {
+            pattern,
+            length: sent.len(),
+            mismatched_positions,
+        }
+    }
+
+    /// number of corrupted bytes
+    pub fn mismatched(&self) -> usize {
+        self.mismatched_positions.len()
+    }
+
+    /// true when every byte came back unchanged
+    pub fn is_clean(&self) -> bool {
+        self.mismatched_positions.is_empty()
+    }
+}
@@ -1,11 +1,53 @@
+#[cfg(feature = "async")]
+pub mod async_serial;
+pub mod at;
+pub mod bridge;
+pub mod capture;
+pub mod checksum;
+pub mod codec;
 pub mod config;
+pub mod correlated;
+pub mod dual;
 pub mod error;
+pub mod framing;
+pub mod hexdump;
+pub mod integrity;
+pub mod lines;
+pub mod mock;
+pub mod modbus;
+pub mod ports;
+pub mod reader;
+pub mod registry;
+#[cfg(feature = "serde")]
+mod serde_config;
 pub mod serial;
 pub mod simple;
+pub mod split;
+pub mod stats;
+pub mod tcp;
+mod throttle;
+pub mod transport;
 
 // main API exports
 pub use error::{BitcoreError, Result};
 pub use simple::{Serial, SerialConfig};
+pub use split::{SerialReader, SerialWriter};
+
+// port settings used by SerialConfig, so users need no direct serialport dependency
+pub use serialport::{DataBits, FlowControl, Parity, StopBits};
 
 // advanced exports for power users
-pub use config::RetryConfig;
+#[cfg(feature = "async")]
+pub use async_serial::AsyncSerial;
+pub use bridge::{BridgeDirection, BridgeStats};
+pub use codec::Codec;
+pub use config::{LineEnding, RetryConfig, Rs485Config, TextDecoding};
+pub use dual::DualSerial;
+pub use integrity::{IntegrityReport, PatternKind};
+pub use lines::Lines;
+pub use ports::{PortDescriptor, PortKind};
+pub use reader::{ReaderHandle, SerialChannel};
+pub use registry::DeviceRegistry;
+pub use stats::SerialStats;
+pub use tcp::TcpTransport;
+pub use transport::Transport;
@@ -0,0 +1,59 @@
+// -- iterator over incoming lines
+//
+//     for line in serial.lines().skip_timeouts() {
+//         let line = line?;
+//         ...
+//     }
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::Serial;
+
+/// lines read with `Serial::read_line`, returned by `Serial::lines`
+///
+/// yields every error as an item. errors that leave the port unusable
+/// (closed, disconnected) end the iteration; others, such as a line that
+/// is not valid utf-8, do not.
+pub struct Lines<'a> {
+    serial: &'a Serial,
+    skip_timeouts: bool,
+    finished: bool,
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(serial: &'a Serial) -> Self {
+        Self {
+            serial,
+            skip_timeouts: false,
+            finished: false,
+        }
+    }
+
+    /// keep waiting through timeouts instead of yielding them as errors
+    pub fn skip_timeouts(mut self) -> Self {
+        self.skip_timeouts = true;
+        self
+    }
+}
+
+impl Iterator for Lines<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match self.serial.read_line() {
+                Err(BitcoreError::Timeout { .. }) if self.skip_timeouts => continue,
+                Err(e) => {
+                    self.finished =
+                        matches!(e, BitcoreError::NotConnected | BitcoreError::LockFailed(_))
+                            || e.is_fatal();
+                    return Some(Err(e));
+                }
+                line => return Some(line),
+            }
+        }
+    }
+}
@@ -0,0 +1,477 @@
+// -- in-memory serial port for tests
+//
+// MockSerial implements serialport::SerialPort on top of byte queues, so a
+// Serial can be driven without hardware or socat:
+//
+//     let mock = MockSerial::new();
+//     let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default())?;
+//     mock.push_rx(b"OK\n");
+//
+// Clones share state, so the test keeps one handle to feed RX data and
+// inspect what the code under test wrote or toggled. MockSerial::pair gives
+// two ends wired to each other, the in-memory counterpart of a socat pty pair.
+
+use crate::capture::{self, CaptureEntry, Direction};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+/// how often a blocking mock read checks for new data
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// something the code under test did to the mock port, in order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockEvent {
+    /// bytes written to the port
+    Write(Vec<u8>),
+    /// output flushed
+    Flush,
+    /// RTS line set to the given level
+    Rts(bool),
+    /// DTR line set to the given level
+    Dtr(bool),
+    /// break condition set (true) or cleared (false)
+    Break(bool),
+    /// buffers discarded
+    Clear(ClearBuffer),
+}
+
+struct MockState {
+    rx: VecDeque<u8>,
+    tx: Vec<u8>,
+    events: Vec<MockEvent>,
+    rts: bool,
+    dtr: bool,
+    cts: bool,
+    dsr: bool,
+    ring_indicator: bool,
+    carrier_detect: bool,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    /// echo every write back into the rx queue
+    loopback: bool,
+    /// remaining entries of a replayed capture
+    script: VecDeque<CaptureEntry>,
+    /// bytes the replayed capture expects to be written next
+    expected_tx: VecDeque<u8>,
+    /// errors returned by the next writes instead of accepting data
+    write_failures: VecDeque<io::ErrorKind>,
+    /// errors returned by the next reads instead of delivering data
+    read_failures: VecDeque<io::ErrorKind>,
+    /// what `bytes_to_write` reports, to simulate a slow transmitter
+    pending_tx: u32,
+    /// most bytes one write accepts, to simulate short writes
+    max_write_len: Option<usize>,
+    /// the only baud rates accepted; others round to the nearest one
+    baud_rates: Option<Vec<u32>>,
+    /// the other end of a `pair`; writes land in its rx queue
+    peer: Weak<Mutex<MockState>>,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        Self {
+            rx: VecDeque::new(),
+            tx: Vec::new(),
+            events: Vec::new(),
+            rts: false,
+            dtr: false,
+            cts: false,
+            dsr: false,
+            ring_indicator: false,
+            carrier_detect: false,
+            baud_rate: 9600,
+            data_bits: DataBits::Eight,
+            flow_control: FlowControl::None,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            timeout: Duration::from_secs(1),
+            loopback: false,
+            script: VecDeque::new(),
+            expected_tx: VecDeque::new(),
+            write_failures: VecDeque::new(),
+            read_failures: VecDeque::new(),
+            pending_tx: 0,
+            max_write_len: None,
+            baud_rates: None,
+            peer: Weak::new(),
+        }
+    }
+}
+
+impl MockState {
+    /// move replayed rx chunks into the rx queue up to the next expected write
+    fn advance_script(&mut self) {
+        while self.expected_tx.is_empty() {
+            match self.script.pop_front() {
+                Some(entry) if entry.direction == Direction::Rx => self.rx.extend(entry.data),
+                Some(entry) => self.expected_tx.extend(entry.data),
+                None => break,
+            }
+        }
+    }
+}
+
+/// in-memory serial port; clones share the same underlying state
+#[derive(Clone, Default)]
+pub struct MockSerial {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockSerial {
+    /// create an empty mock port
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// create a mock port where everything written becomes readable again
+    pub fn loopback() -> Self {
+        let mock = Self::new();
+        mock.state().loopback = true;
+        mock
+    }
+
+    /// create two mock ports wired back to back: what one writes, the other reads
+    pub fn pair() -> (Self, Self) {
+        let (a, b) = (Self::new(), Self::new());
+        a.state().peer = Arc::downgrade(&b.state);
+        b.state().peer = Arc::downgrade(&a.state);
+        (a, b)
+    }
+
+    /// replay a file recorded with `SerialConfig::capture`
+    ///
+    /// recorded reads become available as soon as the writes recorded before them
+    /// have been made; a write that differs from the capture fails with
+    /// `io::ErrorKind::InvalidData`
+    pub fn from_capture<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mock = Self::new();
+        {
+            let mut state = mock.state();
+            state.script = capture::read_capture(path)?.into();
+            state.advance_script();
+        }
+        Ok(mock)
+    }
+
+    /// true once every write of a replayed capture has been made
+    pub fn capture_finished(&self) -> bool {
+        let state = self.state();
+        state.expected_tx.is_empty() && state.script.is_empty()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // a panicking test thread must not take every other assertion down with it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// make the next `count` writes fail with `kind`
+    pub fn fail_next_writes(&self, kind: io::ErrorKind, count: usize) {
+        self.state()
+            .write_failures
+            .extend(std::iter::repeat_n(kind, count));
+    }
+
+    /// injected write failures not yet consumed
+    pub fn pending_write_failures(&self) -> usize {
+        self.state().write_failures.len()
+    }
+
+    /// make the next `count` reads fail with `kind`
+    ///
+    /// `io::ErrorKind::BrokenPipe` stands in for an unplugged device
+    pub fn fail_next_reads(&self, kind: io::ErrorKind, count: usize) {
+        self.state()
+            .read_failures
+            .extend(std::iter::repeat_n(kind, count));
+    }
+
+    /// set how many bytes the port claims are still waiting to be sent
+    pub fn set_pending_tx(&self, count: u32) {
+        self.state().pending_tx = count;
+    }
+
+    /// accept at most `len` bytes per write (`None`: everything), like a
+    /// driver whose transmit buffer is nearly full
+    pub fn set_max_write_len(&self, len: Option<usize>) {
+        self.state().max_write_len = len;
+    }
+
+    /// accept only these baud rates (`None`: any), rounding others to the
+    /// nearest one like a driver with a fixed divisor table
+    pub fn set_supported_baud_rates(&self, rates: Option<Vec<u32>>) {
+        self.state().baud_rates = rates;
+    }
+
+    /// queue bytes for the code under test to read
+    pub fn push_rx(&self, data: &[u8]) {
+        self.state().rx.extend(data);
+    }
+
+    /// all bytes written so far
+    pub fn tx(&self) -> Vec<u8> {
+        self.state().tx.clone()
+    }
+
+    /// take and clear the bytes written so far
+    pub fn take_tx(&self) -> Vec<u8> {
+        std::mem::take(&mut self.state().tx)
+    }
+
+    /// every write and control-line change observed so far
+    pub fn events(&self) -> Vec<MockEvent> {
+        self.state().events.clone()
+    }
+
+    /// current RTS level
+    pub fn rts(&self) -> bool {
+        self.state().rts
+    }
+
+    /// current DTR level
+    pub fn dtr(&self) -> bool {
+        self.state().dtr
+    }
+
+    /// set the CTS level seen by the code under test
+    pub fn set_cts(&self, level: bool) {
+        self.state().cts = level;
+    }
+
+    /// set the DSR level seen by the code under test
+    pub fn set_dsr(&self, level: bool) {
+        self.state().dsr = level;
+    }
+
+    /// set the ring indicator level seen by the code under test
+    pub fn set_ring_indicator(&self, level: bool) {
+        self.state().ring_indicator = level;
+    }
+
+    /// set the carrier detect level seen by the code under test
+    pub fn set_carrier_detect(&self, level: bool) {
+        self.state().carrier_detect = level;
+    }
+}
+
+impl SerialPort for MockSerial {
+    fn name(&self) -> Option<String> {
+        Some("mock".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.state().baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(self.state().data_bits)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(self.state().flow_control)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(self.state().parity)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(self.state().stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.state().timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        let mut state = self.state();
+        state.baud_rate = match &state.baud_rates {
+            Some(rates) => rates
+                .iter()
+                .copied()
+                .min_by_key(|rate| rate.abs_diff(baud_rate))
+                .unwrap_or(baud_rate),
+            None => baud_rate,
+        };
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.state().data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.state().flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.state().parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.state().stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.state().timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        let mut state = self.state();
+        state.rts = level;
+        state.events.push(MockEvent::Rts(level));
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        let mut state = self.state();
+        state.dtr = level;
+        state.events.push(MockEvent::Dtr(level));
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(self.state().cts)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(self.state().dsr)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(self.state().ring_indicator)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(self.state().carrier_detect)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut state = self.state();
+        if let Some(kind) = state.read_failures.pop_front() {
+            return Err(serialport::Error::new(
+                serialport::ErrorKind::Io(kind),
+                "injected mock read failure",
+            ));
+        }
+        Ok(state.rx.len().min(u32::MAX as usize) as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        // writes complete immediately unless a test says otherwise
+        Ok(self.state().pending_tx)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        let mut state = self.state();
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            state.rx.clear();
+        }
+        state.events.push(MockEvent::Clear(buffer_to_clear));
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        self.state().events.push(MockEvent::Break(true));
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.state().events.push(MockEvent::Break(false));
+        Ok(())
+    }
+}
+
+impl Read for MockSerial {
+    /// waits up to the port timeout for data, like a real blocking port
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let start_time = Instant::now();
+        let mut state = self.state();
+        loop {
+            if let Some(kind) = state.read_failures.pop_front() {
+                return Err(io::Error::new(kind, "injected mock read failure"));
+            }
+            if !state.rx.is_empty() {
+                break;
+            }
+            if start_time.elapsed() >= state.timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "no mock data available",
+                ));
+            }
+            drop(state);
+            std::thread::sleep(READ_POLL_INTERVAL);
+            state = self.state();
+        }
+
+        let count = buf.len().min(state.rx.len());
+        for (slot, byte) in buf.iter_mut().zip(state.rx.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+impl Write for MockSerial {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state();
+        if let Some(kind) = state.write_failures.pop_front() {
+            return Err(io::Error::new(kind, "injected mock write failure"));
+        }
+        let buf = &buf[..buf.len().min(state.max_write_len.unwrap_or(usize::MAX))];
+        if !state.expected_tx.is_empty() || !state.script.is_empty() {
+            for (offset, &byte) in buf.iter().enumerate() {
+                if state.expected_tx.pop_front() != Some(byte) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("write does not match capture at byte {offset}"),
+                    ));
+                }
+                state.advance_script();
+            }
+        }
+        state.tx.extend_from_slice(buf);
+        if state.loopback {
+            state.rx.extend(buf);
+        }
+        state.events.push(MockEvent::Write(buf.to_vec()));
+        let peer = state.peer.upgrade();
+        // never hold both ends at once, or two threads writing in opposite
+        // directions could deadlock
+        drop(state);
+
+        if let Some(peer) = peer {
+            peer.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .rx
+                .extend(buf);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.state().events.push(MockEvent::Flush);
+        Ok(())
+    }
+}
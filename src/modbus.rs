@@ -0,0 +1,100 @@
+// -- modbus rtu framing
+//
+// request/response frames are `[slave][function][data...][crc16 lo][crc16 hi]`.
+// a slave that rejects a request answers with the function code's high bit set
+// and a one-byte exception code. the exchange itself is `Serial::modbus_*`.
+
+use crate::checksum::crc16_modbus;
+use crate::error::{BitcoreError, Result};
+use crate::simple::SerialConfig;
+use std::time::Duration;
+
+pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+pub const WRITE_SINGLE_REGISTER: u8 = 0x06;
+
+/// most registers one read holding registers request may ask for
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+/// exception responses are slave, function | 0x80, code and crc
+pub(crate) const EXCEPTION_FRAME_LEN: usize = 5;
+
+/// human readable name of a modbus exception code
+pub fn exception_name(code: u8) -> &'static str {
+    match code {
+        0x01 => "illegal function",
+        0x02 => "illegal data address",
+        0x03 => "illegal data value",
+        0x04 => "slave device failure",
+        0x05 => "acknowledge",
+        0x06 => "slave device busy",
+        0x08 => "memory parity error",
+        0x0a => "gateway path unavailable",
+        0x0b => "gateway target device failed to respond",
+        _ => "unknown exception",
+    }
+}
+
+/// the 3.5 character silence that separates rtu frames
+///
+/// fixed at 1.75ms above 19200 baud, as the spec recommends
+pub fn frame_silence(config: &SerialConfig) -> Duration {
+    if config.baud_rate > 19200 {
+        return Duration::from_micros(1750);
+    }
+    config.wire_time(3.5)
+}
+
+/// `[slave][function][data]` with the crc appended
+pub(crate) fn encode_request(slave: u8, function: u8, data: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(data.len() + 4);
+    frame.push(slave);
+    frame.push(function);
+    frame.extend_from_slice(data);
+    let crc = crc16_modbus(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// check a complete response frame and return the bytes after the function code
+pub(crate) fn decode_response(slave: u8, function: u8, frame: &[u8]) -> Result<&[u8]> {
+    if frame.len() < 4 {
+        return Err(malformed(format!(
+            "{}-byte frame is too short",
+            frame.len()
+        )));
+    }
+
+    let (body, crc) = frame.split_at(frame.len() - 2);
+    let expected = u16::from_le_bytes([crc[0], crc[1]]);
+    let actual = crc16_modbus(body);
+    if expected != actual {
+        return Err(BitcoreError::ChecksumMismatch { expected, actual });
+    }
+
+    if body[0] != slave {
+        return Err(malformed(format!(
+            "reply from slave {} to a request for slave {slave}",
+            body[0]
+        )));
+    }
+    if body[1] == function | 0x80 {
+        return Err(BitcoreError::ModbusException {
+            function,
+            code: body[2],
+        });
+    }
+    if body[1] != function {
+        return Err(malformed(format!(
+            "function {:#04x} in reply to {function:#04x}",
+            body[1]
+        )));
+    }
+
+    Ok(&body[2..])
+}
+
+pub(crate) fn malformed(reason: String) -> BitcoreError {
+    BitcoreError::Deserialize {
+        reason: format!("modbus: {reason}"),
+    }
+}
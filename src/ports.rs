@@ -0,0 +1,172 @@
+// -- port discovery helpers
+
+use serialport::{SerialPortInfo, SerialPortType};
+
+/// what kind of device backs a port
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortKind {
+    Usb,
+    Pci,
+    Bluetooth,
+    /// built-in uarts, pseudo terminals and anything the os does not classify
+    Unknown,
+}
+
+/// flat description of a listed port, for port pickers and logs
+///
+/// the usb fields are `None` for other kinds of port, and also when the
+/// device does not report them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortDescriptor {
+    pub name: String,
+    pub kind: PortKind,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+}
+
+impl From<SerialPortInfo> for PortDescriptor {
+    fn from(info: SerialPortInfo) -> Self {
+        let mut descriptor = PortDescriptor {
+            name: info.port_name,
+            kind: PortKind::Unknown,
+            manufacturer: None,
+            product: None,
+            serial_number: None,
+            vid: None,
+            pid: None,
+        };
+        match info.port_type {
+            SerialPortType::UsbPort(usb) => {
+                descriptor.kind = PortKind::Usb;
+                descriptor.manufacturer = usb.manufacturer;
+                descriptor.product = usb.product;
+                descriptor.serial_number = usb.serial_number;
+                descriptor.vid = Some(usb.vid);
+                descriptor.pid = Some(usb.pid);
+            }
+            SerialPortType::PciPort => descriptor.kind = PortKind::Pci,
+            SerialPortType::BluetoothPort => descriptor.kind = PortKind::Bluetooth,
+            SerialPortType::Unknown => {}
+        }
+        descriptor
+    }
+}
+
+/// match a port name against a shell-style glob
+///
+/// supports `*` (any run of characters), `?` (any single character) and
+/// `[...]` character classes with ranges (`[0-9]`) and negation (`[!0]`)
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` in the pattern and the name index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            Some('[') => {
+                if let Some((matched, len)) = match_class(&pattern[p..], name[n]) {
+                    if matched {
+                        p += len;
+                        n += 1;
+                        continue;
+                    }
+                } else if name[n] == '[' {
+                    // unterminated class, treat '[' literally
+                    p += 1;
+                    n += 1;
+                    continue;
+                }
+            }
+            Some(&c) if c == name[n] => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        // mismatch: let the last `*` swallow one more character
+        match backtrack {
+            Some((star_p, star_n)) => {
+                backtrack = Some((star_p, star_n + 1));
+                p = star_p + 1;
+                n = star_n + 1;
+            }
+            None => return false,
+        }
+    }
+
+    // only trailing stars may remain
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// match `c` against the character class at the start of `class`
+///
+/// returns whether it matched and how many pattern characters the class spans,
+/// or `None` if the class is not terminated
+fn match_class(class: &[char], c: char) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negate = matches!(class.get(i), Some('!') | Some('^'));
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while let Some(&start) = class.get(i) {
+        if start == ']' && !first {
+            return Some((matched != negate, i + 1));
+        }
+        first = false;
+
+        if class.get(i + 1) == Some(&'-') && class.get(i + 2).is_some_and(|&e| e != ']') {
+            let end = class[i + 2];
+            matched |= start <= c && c <= end;
+            i += 3;
+        } else {
+            matched |= start == c;
+            i += 1;
+        }
+    }
+
+    None
+}
+
+/// keep only the ports whose name matches the glob `pattern`
+pub fn filter_by_glob(ports: Vec<SerialPortInfo>, pattern: &str) -> Vec<SerialPortInfo> {
+    ports
+        .into_iter()
+        .filter(|port| glob_match(pattern, &port.port_name))
+        .collect()
+}
+
+/// keep only USB ports with vendor id `vid` and, if given, product id `pid`
+pub fn filter_by_usb(
+    ports: Vec<SerialPortInfo>,
+    vid: u16,
+    pid: Option<u16>,
+) -> Vec<SerialPortInfo> {
+    ports
+        .into_iter()
+        .filter(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) => usb.vid == vid && pid.is_none_or(|pid| usb.pid == pid),
+            _ => false,
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+// -- background reading
+//
+// Serial::spawn_reader runs a read loop on its own thread and hands every
+// received chunk to a callback, for event-driven programs (guis, loggers)
+// that would otherwise poll `read` on a timer:
+//
+//     let reader = serial.spawn_reader(|data| println!("got {data:?}"));
+//     ...
+//     reader.stop();
+//
+// the thread holds a clone of the Serial; stopping (or dropping) the handle
+// ends the loop, joins the thread and releases that clone.
+//
+// Serial::reader_channel does the same into a bounded channel, for event
+// loops that wait on several sources, and takes outgoing data over a second
+// channel:
+//
+//     let channel = serial.reader_channel(16);
+//     channel.sender().send(b"ping".to_vec())?;
+//     let reply = channel.receiver().recv()?;
+//
+// when the receiver is full the reader stops reading, so unread input backs
+// up into the driver (and, with flow control, the peer) instead of piling up
+// in memory.
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::Serial;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{debug, trace, warn};
+
+/// how long one read (or a wait on a channel) lasts before the stop flag is
+/// checked again
+const READ_SLICE: Duration = Duration::from_millis(50);
+
+/// received bytes handed over per callback at most
+const CHUNK_LEN: usize = 1024;
+
+/// handle on a background reader; stops it when dropped
+pub struct ReaderHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ReaderHandle {
+    pub(crate) fn spawn(serial: Serial, on_data: impl Fn(&[u8]) + Send + 'static) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || {
+                read_loop(&serial, &stop, |read| match read {
+                    Ok(data) => on_data(data),
+                    Err(e) => warn!("background reader error: {}", e),
+                })
+            })
+        };
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// stop reading and wait for the thread to finish
+    ///
+    /// a callback already running completes first; none runs afterwards
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    /// whether the reader thread is still running
+    ///
+    /// false once stopped, or if the callback panicked
+    pub fn is_running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ReaderHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// a background reader feeding a bounded channel, plus a writer fed by one
+///
+/// received chunks and any read or write errors arrive on `receiver`; data
+/// sent through `sender` is written in order. dropping this stops both
+/// threads and releases their `Serial` clones.
+pub struct SerialChannel {
+    receiver: Receiver<Result<Vec<u8>>>,
+    sender: Sender<Vec<u8>>,
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl SerialChannel {
+    pub(crate) fn spawn(serial: Serial, capacity: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (event_tx, receiver) = mpsc::sync_channel(capacity);
+        let (sender, outgoing) = mpsc::channel::<Vec<u8>>();
+
+        let reader = {
+            let serial = serial.clone();
+            let stop = Arc::clone(&stop);
+            let event_tx = event_tx.clone();
+            thread::spawn(move || {
+                read_loop(&serial, &stop, |read| {
+                    deliver(&event_tx, read.map(<[u8]>::to_vec), &stop);
+                })
+            })
+        };
+        let writer = {
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || write_loop(&serial, &outgoing, &event_tx, &stop))
+        };
+
+        Self {
+            receiver,
+            sender,
+            stop,
+            threads: vec![reader, writer],
+        }
+    }
+
+    /// received chunks, and errors from either direction
+    pub fn receiver(&self) -> &Receiver<Result<Vec<u8>>> {
+        &self.receiver
+    }
+
+    /// a sender for data to write; clones may be moved to other threads
+    ///
+    /// sends are not bounded; write errors arrive on `receiver`
+    pub fn sender(&self) -> Sender<Vec<u8>> {
+        self.sender.clone()
+    }
+}
+
+impl Drop for SerialChannel {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// read until stopped, passing each chunk or error to `on_read`
+///
+/// like the heartbeat, the loop idles while the port is disconnected, so it
+/// picks up again after a `reconnect`
+fn read_loop(serial: &Serial, stop: &AtomicBool, mut on_read: impl FnMut(Result<&[u8]>)) {
+    let mut buffer = [0u8; CHUNK_LEN];
+
+    while !stop.load(Ordering::Relaxed) {
+        match serial.read_for(&mut buffer, READ_SLICE) {
+            Ok(0) => {}
+            Ok(bytes_read) => on_read(Ok(&buffer[..bytes_read])),
+            Err(BitcoreError::Timeout { .. }) => {}
+            Err(BitcoreError::NotConnected) => {
+                trace!("background reader idle: not connected");
+                thread::sleep(READ_SLICE);
+            }
+            Err(e) => {
+                on_read(Err(e));
+                thread::sleep(READ_SLICE);
+            }
+        }
+    }
+    debug!("background reader stopped");
+}
+
+/// write whatever arrives on `outgoing` until stopped
+fn write_loop(
+    serial: &Serial,
+    outgoing: &Receiver<Vec<u8>>,
+    events: &SyncSender<Result<Vec<u8>>>,
+    stop: &AtomicBool,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        match outgoing.recv_timeout(READ_SLICE) {
+            Ok(data) => {
+                if let Err(e) = serial.write_all(&data) {
+                    deliver(events, Err(e), stop);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    debug!("background writer stopped");
+}
+
+/// queue `event`, waiting while the channel is full
+///
+/// gives up once stopped, so a receiver nobody drains cannot block shutdown
+fn deliver(events: &SyncSender<Result<Vec<u8>>>, mut event: Result<Vec<u8>>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        match events.try_send(event) {
+            Ok(()) | Err(TrySendError::Disconnected(_)) => return,
+            Err(TrySendError::Full(returned)) => {
+                event = returned;
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// -- named devices for multi-instrument setups
+//
+//     let mut registry = DeviceRegistry::new();
+//     registry.register("dmm", "/dev/ttyUSB0", &SerialConfig::new(9600))?;
+//     let reading = registry.send_recv("dmm", b"MEAS:VOLT?\n", b'\n')?;
+
+use crate::error::{BitcoreError, Result};
+use crate::simple::{deadline_after, Serial, SerialConfig};
+use std::collections::HashMap;
+use tracing::{debug, info};
+
+/// connections looked up by name
+#[derive(Default)]
+pub struct DeviceRegistry {
+    devices: HashMap<String, Serial>,
+}
+
+impl DeviceRegistry {
+    /// create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// open `port` with `config` and register it under `name`
+    pub fn register(&mut self, name: &str, port: &str, config: &SerialConfig) -> Result<()> {
+        if self.devices.contains_key(name) {
+            return Err(BitcoreError::InvalidParameter {
+                param: "name".to_string(),
+                reason: format!("device {name} is already registered"),
+            });
+        }
+
+        let serial = Serial::with_config(port, config)?;
+        info!("registered device {} on {}", name, port);
+        self.devices.insert(name.to_string(), serial);
+        Ok(())
+    }
+
+    /// the connection registered under `name`
+    pub fn get(&self, name: &str) -> Option<&Serial> {
+        self.devices.get(name)
+    }
+
+    /// unregister `name` and return its connection
+    pub fn remove(&mut self, name: &str) -> Option<Serial> {
+        self.devices.remove(name)
+    }
+
+    /// registered device names, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.devices.keys().map(String::as_str)
+    }
+
+    /// send `request` to `name` and read its reply up to and including `delimiter`
+    ///
+    /// stale input is discarded first; the write and the read share the
+    /// device's configured timeout
+    pub fn send_recv(&self, name: &str, request: &[u8], delimiter: u8) -> Result<Vec<u8>> {
+        let serial = self
+            .get(name)
+            .ok_or_else(|| BitcoreError::InvalidParameter {
+                param: "name".to_string(),
+                reason: format!("no device registered as {name}"),
+            })?;
+
+        let deadline = deadline_after(serial.settings().timeout);
+        serial.discard_input()?;
+        serial.write_all_deadline(request, deadline)?;
+        let reply = serial.read_until_deadline(delimiter, deadline)?;
+        debug!("{} replied with {} bytes", name, reply.len());
+        Ok(reply)
+    }
+}
@@ -0,0 +1,167 @@
+// -- config file (de)serialization, enabled by the `serde` feature
+//
+// durations are plain milliseconds and the serialport enums use the names
+// people write by hand, so a config file reads like
+//
+//     baud_rate = 115200
+//     timeout_ms = 500
+//     data_bits = 8
+//     parity = "none"
+//     stop_bits = 1
+//     flow_control = "hardware"
+
+use serde::de::{Deserializer, Error, Unexpected};
+use serde::{Deserialize, Serialize, Serializer};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+use std::time::Duration;
+
+fn duration_ms(duration: &Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
+
+/// `Duration` as whole milliseconds
+pub(crate) mod millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration_ms(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+/// `Option<Duration>` as whole milliseconds
+pub(crate) mod opt_millis {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value.as_ref().map(duration_ms).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<u64>::deserialize(deserializer).map(|ms| ms.map(Duration::from_millis))
+    }
+}
+
+/// heartbeat `(payload, interval)` as `{ payload, interval_ms }`
+pub(crate) mod heartbeat {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Heartbeat {
+        payload: Vec<u8>,
+        interval_ms: u64,
+    }
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<(Vec<u8>, Duration)>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        value
+            .as_ref()
+            .map(|(payload, interval)| Heartbeat {
+                payload: payload.clone(),
+                interval_ms: duration_ms(interval),
+            })
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<(Vec<u8>, Duration)>, D::Error> {
+        let heartbeat = Option::<Heartbeat>::deserialize(deserializer)?;
+        Ok(heartbeat.map(|h| (h.payload, Duration::from_millis(h.interval_ms))))
+    }
+}
+
+/// `DataBits` as 5, 6, 7 or 8
+pub(crate) mod data_bits {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &DataBits, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DataBits, D::Error> {
+        let bits = u8::deserialize(deserializer)?;
+        DataBits::try_from(bits).map_err(|_| {
+            D::Error::invalid_value(Unexpected::Unsigned(bits.into()), &"5, 6, 7 or 8")
+        })
+    }
+}
+
+/// `StopBits` as 1 or 2
+pub(crate) mod stop_bits {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &StopBits, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(u8::from(*value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StopBits, D::Error> {
+        let bits = u8::deserialize(deserializer)?;
+        StopBits::try_from(bits)
+            .map_err(|_| D::Error::invalid_value(Unexpected::Unsigned(bits.into()), &"1 or 2"))
+    }
+}
+
+/// `Parity` as "none", "odd" or "even"
+pub(crate) mod parity {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Parity, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match value {
+            Parity::None => "none",
+            Parity::Odd => "odd",
+            Parity::Even => "even",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Parity, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Ok(Parity::None),
+            "odd" => Ok(Parity::Odd),
+            "even" => Ok(Parity::Even),
+            _ => Err(D::Error::invalid_value(
+                Unexpected::Str(&name),
+                &"\"none\", \"odd\" or \"even\"",
+            )),
+        }
+    }
+}
+
+/// `FlowControl` as "none", "software" or "hardware"
+pub(crate) mod flow_control {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &FlowControl, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(match value {
+            FlowControl::None => "none",
+            FlowControl::Software => "software",
+            FlowControl::Hardware => "hardware",
+        })
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FlowControl, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Ok(FlowControl::None),
+            "software" => Ok(FlowControl::Software),
+            "hardware" => Ok(FlowControl::Hardware),
+            _ => Err(D::Error::invalid_value(
+                Unexpected::Str(&name),
+                &"\"none\", \"software\" or \"hardware\"",
+            )),
+        }
+    }
+}
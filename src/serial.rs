@@ -1,25 +1,100 @@
 // -- lower level implementation
 // handles direct interaction with the serial port
 
+use crate::capture::{CaptureWriter, Direction};
+use crate::hexdump::hex_dump;
+use crate::transport::Transport;
 use serialport::{ClearBuffer, SerialPort, SerialPortBuilder, SerialPortInfo};
 use std::io::{self, Read, Write};
+#[cfg(target_os = "linux")]
+use std::os::unix::io::{AsRawFd, RawFd};
 use std::thread;
 use std::time::{Duration, Instant};
 use tracing::{debug, error, trace, warn};
 
 /// default polling interval for read operations (optimized from 100ms to 10ms)
-const DEFAULT_POLL_INTERVAL_MS: u64 = 10;
+pub(crate) const DEFAULT_POLL_INTERVAL_MS: u64 = 10;
+
+/// sleeps until a port may have input, without needing the port itself
+///
+/// natively opened ports on linux are polled for input, so a reader wakes as
+/// soon as bytes arrive instead of once per poll interval. other transports
+/// fall back to sleeping one poll interval.
+///
+/// the descriptor is only borrowed: once the connection lock is released, a
+/// disconnect may close it and a later open may reuse its number. a poll
+/// therefore never waits longer than one poll interval, after which the
+/// caller takes the lock and looks at the connection again.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadWaiter {
+    #[cfg(target_os = "linux")]
+    fd: Option<RawFd>,
+    poll_interval: Duration,
+}
+
+impl ReadWaiter {
+    /// block until input may be available, or at most `timeout` (and never
+    /// more than one poll interval)
+    ///
+    /// may return early without input (a hangup, a signal, another reader
+    /// taking the bytes); callers re-check and wait again
+    pub(crate) fn wait(&self, timeout: Duration) {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = self.fd {
+            let mut pollfd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout_ms = timeout
+                .min(self.poll_interval)
+                .as_micros()
+                .div_ceil(1000)
+                .min(libc::c_int::MAX as u128) as libc::c_int;
+            // SAFETY: pollfd is a single valid entry, and poll only reports
+            // readiness, it never reads from or changes the descriptor. if a
+            // disconnect closed it since, poll reports POLLNVAL at once; if
+            // the number was reused by another file meanwhile, the wait is on
+            // that file instead, for one poll interval at most (see above)
+            unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+            return;
+        }
+
+        thread::sleep(self.poll_interval.min(timeout));
+    }
+}
 
 pub struct SerialConnection {
-    port: Box<dyn SerialPort>,
+    port: Box<dyn Transport>,
     poll_interval: Duration,
+    capture: Option<CaptureWriter>,
+    /// hex-dump every buffer read or written at trace level
+    trace_bytes: bool,
+    /// descriptor of a natively opened port, used for ioctls serialport lacks
+    #[cfg(target_os = "linux")]
+    raw_fd: Option<RawFd>,
+    /// overrun counter at open, `None` if the driver does not report one
+    overrun_baseline: Option<u32>,
+    /// overruns seen by the last check, to warn when the count grows
+    last_overruns: u32,
 }
 
 impl SerialConnection {
     pub fn new(port: Box<dyn SerialPort>) -> Self {
+        Self::from_transport(Box::new(port))
+    }
+
+    /// connection over any transport, not just a serialport port
+    pub fn from_transport(port: Box<dyn Transport>) -> Self {
         SerialConnection {
             port,
             poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+            capture: None,
+            trace_bytes: false,
+            #[cfg(target_os = "linux")]
+            raw_fd: None,
+            overrun_baseline: None,
+            last_overruns: 0,
         }
     }
 
@@ -28,18 +103,146 @@ impl SerialConnection {
         self
     }
 
+    pub(crate) fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// handle for waiting on input after the connection lock is released
+    pub(crate) fn read_waiter(&self) -> ReadWaiter {
+        ReadWaiter {
+            #[cfg(target_os = "linux")]
+            fd: self.raw_fd,
+            poll_interval: self.poll_interval,
+        }
+    }
+
+    pub(crate) fn with_capture(mut self, capture: CaptureWriter) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    pub(crate) fn with_trace_bytes(mut self, trace_bytes: bool) -> Self {
+        self.trace_bytes = trace_bytes;
+        self
+    }
+
+    /// log `data` as a hex dump if byte tracing is on
+    fn dump(&self, direction: Direction, data: &[u8]) {
+        // checked first so a disabled trace level never pays for the formatting
+        if self.trace_bytes && tracing::enabled!(tracing::Level::TRACE) {
+            let label = match direction {
+                Direction::Tx => "tx",
+                Direction::Rx => "rx",
+            };
+            trace!("{} {} bytes\n{}", label, data.len(), hex_dump(data));
+        }
+    }
+
+    /// second connection on a cloned handle of the same port
+    ///
+    /// keeps the poll interval and capture file; overrun counting stays with
+    /// the original
+    pub(crate) fn try_clone_connection(&self) -> serialport::Result<Self> {
+        let mut clone = Self::from_transport(self.port.try_clone_transport()?)
+            .with_poll_interval(self.poll_interval)
+            .with_trace_bytes(self.trace_bytes);
+        if let Some(capture) = &self.capture {
+            clone.capture = Some(capture.try_clone()?);
+        }
+        Ok(clone)
+    }
+
     pub fn list() -> io::Result<Vec<SerialPortInfo>> {
         let ports = serialport::available_ports()?;
         Ok(ports)
     }
 
     pub fn connect(spbuild: SerialPortBuilder) -> io::Result<Self> {
+        Ok(Self::open(spbuild)?)
+    }
+
+    /// `connect`, keeping serialport's error kind
+    ///
+    /// the io conversion turns `NoDevice` (port busy or locked) into
+    /// `NotFound`, which can no longer be told apart from a missing device
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open(spbuild: SerialPortBuilder) -> serialport::Result<Self> {
+        let port = spbuild.open_native()?;
+        let fd = port.as_raw_fd();
+
+        let mut connection = Self::new(Box::new(port));
+        connection.raw_fd = Some(fd);
+        connection.overrun_baseline = connection.read_overruns().ok();
+        Ok(connection)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn open(spbuild: SerialPortBuilder) -> serialport::Result<Self> {
         let port = spbuild.open()?;
 
-        Ok(Self {
-            port,
-            poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
-        })
+        Ok(Self::new(port))
+    }
+
+    /// receive overruns since the port was opened
+    ///
+    /// fails with `io::ErrorKind::Unsupported` when the driver keeps no counter
+    pub(crate) fn overrun_count(&mut self) -> io::Result<u32> {
+        let Some(baseline) = self.overrun_baseline else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "port does not report overrun counts",
+            ));
+        };
+
+        let overruns = self.read_overruns()?.wrapping_sub(baseline);
+        if overruns > self.last_overruns {
+            warn!(
+                "serial receive overrun: {} bytes lost since last check ({} total)",
+                overruns - self.last_overruns,
+                overruns
+            );
+        }
+        self.last_overruns = overruns;
+        Ok(overruns)
+    }
+
+    /// raw hardware + buffer overrun counter from the driver
+    #[cfg(target_os = "linux")]
+    fn read_overruns(&self) -> io::Result<u32> {
+        /// `struct serial_icounter_struct` from linux/serial.h
+        #[repr(C)]
+        #[derive(Default)]
+        struct SerialIcounter {
+            cts: libc::c_int,
+            dsr: libc::c_int,
+            rng: libc::c_int,
+            dcd: libc::c_int,
+            rx: libc::c_int,
+            tx: libc::c_int,
+            frame: libc::c_int,
+            overrun: libc::c_int,
+            parity: libc::c_int,
+            brk: libc::c_int,
+            buf_overrun: libc::c_int,
+            reserved: [libc::c_int; 9],
+        }
+
+        let Some(fd) = self.raw_fd else {
+            return Err(io::Error::from(io::ErrorKind::Unsupported));
+        };
+        let mut counters = SerialIcounter::default();
+        // SAFETY: fd belongs to the port owned by self, and TIOCGICOUNT writes
+        // exactly one serial_icounter_struct into the pointer it is given
+        let result = unsafe { libc::ioctl(fd, libc::TIOCGICOUNT, &mut counters) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((counters.overrun as u32).wrapping_add(counters.buf_overrun as u32))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn read_overruns(&self) -> io::Result<u32> {
+        Err(io::Error::from(io::ErrorKind::Unsupported))
     }
 
     pub fn disconnect(mut self) -> io::Result<()> {
@@ -144,7 +347,9 @@ impl SerialPort for SerialConnection {
     }
 
     fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
-        self.port.try_clone()
+        Ok(Box::new(Self::from_transport(
+            self.port.try_clone_transport()?,
+        )))
     }
 
     fn set_break(&self) -> serialport::Result<()> {
@@ -156,6 +361,79 @@ impl SerialPort for SerialConnection {
     }
 }
 
+impl SerialConnection {
+    /// one poll: read whatever is available right now, or return 0 without waiting
+    pub(crate) fn try_read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.bytes_available().inspect_err(|e| {
+            error!("error checking bytes to read: {}", e);
+        })?;
+        if bytes == 0 {
+            return Ok(0);
+        }
+
+        trace!("found {} bytes available to read", bytes);
+        match self.port.read(buf) {
+            Ok(bytes_read) => {
+                if bytes_read > 0 {
+                    debug!("successfully read {} bytes", bytes_read);
+                    self.dump(Direction::Rx, &buf[..bytes_read]);
+                    if let Some(capture) = self.capture.as_mut() {
+                        capture.record(Direction::Rx, &buf[..bytes_read]);
+                    }
+                    if self.overrun_baseline.is_some() {
+                        // only for the warning; the read itself succeeded
+                        let _ = self.overrun_count();
+                    }
+                }
+                Ok(bytes_read)
+            }
+            Err(e) => {
+                error!("error reading bytes: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// bytes waiting in the input buffer, keeping the os error code on failure
+    /// so callers can tell an unplugged device from a passing glitch
+    #[cfg(target_os = "linux")]
+    fn bytes_available(&self) -> io::Result<u32> {
+        let Some(fd) = self.raw_fd else {
+            return Ok(self.port.bytes_to_read()?);
+        };
+
+        let mut bytes: libc::c_int = 0;
+        // SAFETY: fd belongs to the port owned by self, and FIONREAD writes
+        // one c_int into the pointer it is given
+        if unsafe { libc::ioctl(fd, libc::FIONREAD, &mut bytes) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if bytes > 0 {
+            return Ok(bytes as u32);
+        }
+
+        // a hung-up tty has nothing to read but never errors on FIONREAD;
+        // poll reports the hangup so the read can fail the way it would on
+        // a blocking port
+        let mut pollfd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: pollfd is a single valid entry and a zero timeout never blocks
+        let ready = unsafe { libc::poll(&mut pollfd, 1, 0) };
+        if ready > 0 && pollfd.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EIO));
+        }
+        Ok(0)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn bytes_available(&self) -> io::Result<u32> {
+        Ok(self.port.bytes_to_read()?)
+    }
+}
+
 impl Read for SerialConnection {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         let start_time = Instant::now();
@@ -163,35 +441,17 @@ impl Read for SerialConnection {
 
         trace!("starting read operation with timeout {:?}", timeout);
 
-        while start_time.elapsed() < timeout {
-            match self.port.bytes_to_read() {
-                Ok(bytes) => {
-                    if bytes > 0 {
-                        trace!("found {} bytes available to read", bytes);
-                        match self.port.read(buf) {
-                            Ok(bytes_read) => {
-                                if bytes_read > 0 {
-                                    debug!("successfully read {} bytes", bytes_read);
-                                    return Ok(bytes_read);
-                                }
-                            }
-                            Err(e) => {
-                                error!("error reading bytes: {}", e);
-                                return Err(io::Error::other(format!("error reading bytes: {e}")));
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("error checking bytes to read: {}", e);
-                    return Err(io::Error::other(format!(
-                        "error checking bytes to read: {e}"
-                    )));
-                }
+        loop {
+            let bytes_read = self.try_read(buf)?;
+            if bytes_read > 0 {
+                return Ok(bytes_read);
+            }
+            let elapsed = start_time.elapsed();
+            if elapsed >= timeout {
+                break;
             }
 
-            // optimized polling interval
-            thread::sleep(self.poll_interval);
+            self.read_waiter().wait(timeout - elapsed);
         }
 
         // read timeout elapsed
@@ -209,6 +469,10 @@ impl Write for SerialConnection {
         match self.port.write(buf) {
             Ok(bytes_written) => {
                 debug!("successfully wrote {} bytes", bytes_written);
+                self.dump(Direction::Tx, &buf[..bytes_written]);
+                if let Some(capture) = self.capture.as_mut() {
+                    capture.record(Direction::Tx, &buf[..bytes_written]);
+                }
                 Ok(bytes_written)
             }
             Err(e) => {
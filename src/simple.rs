@@ -9,32 +9,242 @@
 // For advanced use cases requiring fine-grained control,
 // see api.rs for the lower-level interface.
 
+use crate::at::{self, FinalResult};
+use crate::bridge::{self, BridgeDirection, BridgeStats};
+use crate::capture::CaptureWriter;
+use crate::checksum;
+use crate::codec::Codec;
+use crate::config::{self, LineEnding, RetryConfig, Rs485Config, TextDecoding};
 use crate::error::{BitcoreError, Result};
-use crate::serial::SerialConnection;
-use serialport::{DataBits, FlowControl, Parity, SerialPort, SerialPortInfo, StopBits};
-use std::io::{Read, Write};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tracing::{debug, error, info, warn};
+use crate::framing::{self, slip, LengthPrefix};
+use crate::integrity::{IntegrityReport, PatternKind};
+use crate::lines::Lines;
+use crate::mock::MockSerial;
+use crate::modbus;
+use crate::ports::{self, PortDescriptor};
+use crate::reader::{ReaderHandle, SerialChannel};
+use crate::serial::{SerialConnection, DEFAULT_POLL_INTERVAL_MS};
+use crate::split::{SerialReader, SerialWriter};
+use crate::stats::{SerialStats, StatsCounters};
+use crate::tcp::TcpTransport;
+use crate::throttle::RateLimiter;
+use crate::transport::Transport;
+use serialport::{
+    ClearBuffer, DataBits, FlowControl, Parity, SerialPort, SerialPortBuilder, SerialPortInfo,
+    StopBits,
+};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Write;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, TryLockError, Weak};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, trace, warn};
+
+/// how often the heartbeat thread checks whether a heartbeat is due
+const HEARTBEAT_CHECK_INTERVAL_MS: u64 = 50;
+
+/// scratch buffer size for reads that collect an unknown amount of data
+const READ_CHUNK_LEN: usize = 256;
+
+/// how far (in percent) the rate a driver picked may stray from the requested
+/// one before `set_baud_rate` rejects it; uarts usually cope with about 2%
+const BAUD_RATE_TOLERANCE_PERCENT: u64 = 2;
+
+/// longest `detect_device` waits for a probed port to answer; a shorter
+/// configured timeout is used as is
+const PROBE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// longest line `read_line` accepts before giving up with `LineTooLong`
+pub const DEFAULT_MAX_LINE_LEN: usize = 64 * 1024;
+
+/// config used by `Serial::new`, set with `Serial::set_global_default_config`
+static GLOBAL_DEFAULT_CONFIG: RwLock<Option<SerialConfig>> = RwLock::new(None);
+
+/// transform applied to bytes on their way to or from the port
+type ByteFilter = Arc<dyn Fn(&[u8]) -> Vec<u8> + Send + Sync>;
+
+/// the connection shared by all clones of a `Serial`
+///
+/// closes the port when dropped, i.e. once no clone or background thread
+/// holds it any more
+struct ConnectionSlot(Mutex<Option<SerialConnection>>);
+
+impl Deref for ConnectionSlot {
+    type Target = Mutex<Option<SerialConnection>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 /// simple serial connection that handles everything automatically
+///
+/// clones are cheap handles on the same port: a write through one is read
+/// back through any other, settings changes reach all of them, and the port
+/// stays open until the last clone is dropped (or `disconnect` is called)
 #[derive(Clone)]
 pub struct Serial {
-    connection: Arc<Mutex<Option<SerialConnection>>>,
-    timeout: Duration,
-    retries: usize,
+    connection: Arc<ConnectionSlot>,
+    path: Arc<Mutex<Option<String>>>,
+    /// shared so runtime changes (`set_baud_rate`) reach every clone and reconnects
+    config: Arc<RwLock<SerialConfig>>,
+    last_write: Arc<Mutex<Option<Instant>>>,
+    /// last user read or write, for the idle watchdog
+    last_activity: Arc<Mutex<Instant>>,
+    write_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    read_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    write_filter: Arc<RwLock<Option<ByteFilter>>>,
+    read_filter: Arc<RwLock<Option<ByteFilter>>>,
+    /// received bytes taken from the port but not returned yet: read filter
+    /// output that did not fit the caller's buffer, and whatever a line or
+    /// delimiter read pulled in past its end. every read drains it first
+    pending_rx: Arc<Mutex<VecDeque<u8>>>,
+    /// last connection-fatal error, cleared by the next successful read or write
+    last_error: Arc<Mutex<Option<BitcoreError>>>,
+    stats: Arc<StatsCounters>,
+}
+
+// try_lock throughout: formatting must not block (or deadlock a logger) while
+// another thread is mid-transfer
+impl fmt::Debug for Serial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let locked = format_args!("<locked>");
+        let mut debug = f.debug_struct("Serial");
+        match self.path.try_lock() {
+            Ok(path) => debug.field("port", &*path),
+            Err(TryLockError::Poisoned(e)) => debug.field("port", &*e.into_inner()),
+            Err(TryLockError::WouldBlock) => debug.field("port", &locked),
+        };
+        match self.connection.try_lock() {
+            Ok(conn) => debug.field("connected", &conn.is_some()),
+            Err(TryLockError::Poisoned(e)) => debug.field("connected", &e.into_inner().is_some()),
+            Err(TryLockError::WouldBlock) => debug.field("connected", &locked),
+        };
+        match self.config.try_read() {
+            Ok(config) => debug.field("config", &*config),
+            Err(TryLockError::Poisoned(e)) => debug.field("config", &*e.into_inner()),
+            Err(TryLockError::WouldBlock) => debug.field("config", &locked),
+        };
+        debug.finish_non_exhaustive()
+    }
+}
+
+// lets a Serial stand in wherever an io::Write is expected. write_all and
+// write_fmt go to the inherent versions so a whole buffer or formatted string
+// is one transfer, not a series of `write` calls each with its own timeout
+impl Write for &Serial {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(Serial::write(self, buf)?)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        Ok(Serial::write_all(self, buf)?)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> std::io::Result<()> {
+        Ok(Serial::write_fmt(self, args)?)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(Serial::flush(self)?)
+    }
+}
+
+impl Write for Serial {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Write::write(&mut &*self, buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        Write::write_all(&mut &*self, buf)
+    }
+
+    fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> std::io::Result<()> {
+        Write::write_fmt(&mut &*self, args)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Write::flush(&mut &*self)
+    }
 }
 
 /// simplified configuration for serial connections
+///
+/// with the `serde` feature this (de)serializes from config files; missing
+/// keys take their default and durations are given in milliseconds
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct SerialConfig {
     pub baud_rate: u32,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "timeout_ms", with = "crate::serde_config::millis")
+    )]
     pub timeout: Duration,
     pub retries: usize,
+    /// backoff between read and write retries; `None` means `RetryConfig::new(retries)`
+    pub retry: Option<RetryConfig>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_config::data_bits"))]
     pub data_bits: DataBits,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_config::parity"))]
     pub parity: Parity,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_config::stop_bits"))]
     pub stop_bits: StopBits,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_config::flow_control"))]
     pub flow_control: FlowControl,
+    pub text_decoding: TextDecoding,
+    pub line_ending: LineEnding,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_config::heartbeat"))]
+    pub heartbeat: Option<(Vec<u8>, Duration)>,
+    pub capture: Option<PathBuf>,
+    pub write_rate_limit: Option<u32>,
+    pub read_rate_limit: Option<u32>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "open_settle_delay_ms", with = "crate::serde_config::millis")
+    )]
+    pub open_settle_delay: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "open_timeout_ms", with = "crate::serde_config::opt_millis")
+    )]
+    pub open_timeout: Option<Duration>,
+    pub exclusive: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "idle_timeout_ms", with = "crate::serde_config::opt_millis")
+    )]
+    pub idle_timeout: Option<Duration>,
+    pub auto_flush: bool,
+    pub adaptive_timeout: bool,
+    pub reconnect: bool,
+    pub max_reconnect_attempts: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "poll_interval_ms", with = "crate::serde_config::millis")
+    )]
+    pub poll_interval: Duration,
+    pub rs485: Rs485Config,
+    pub trace_bytes: bool,
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "inter_byte_timeout_ms",
+            with = "crate::serde_config::opt_millis"
+        )
+    )]
+    pub inter_byte_timeout: Option<Duration>,
+    pub clear_before_write: bool,
+    pub strict_writes: bool,
+    pub clear_input_before_exchange: bool,
 }
 
 impl Default for SerialConfig {
@@ -43,10 +253,32 @@ impl Default for SerialConfig {
             baud_rate: 9600,
             timeout: Duration::from_secs(1),
             retries: 3,
+            retry: None,
             data_bits: DataBits::Eight,
             parity: Parity::None,
             stop_bits: StopBits::One,
             flow_control: FlowControl::None,
+            text_decoding: TextDecoding::default(),
+            line_ending: LineEnding::default(),
+            heartbeat: None,
+            capture: None,
+            write_rate_limit: None,
+            read_rate_limit: None,
+            open_settle_delay: Duration::ZERO,
+            open_timeout: None,
+            exclusive: true,
+            idle_timeout: None,
+            auto_flush: false,
+            adaptive_timeout: false,
+            reconnect: false,
+            max_reconnect_attempts: 3,
+            poll_interval: Duration::from_millis(DEFAULT_POLL_INTERVAL_MS),
+            rs485: Rs485Config::default(),
+            trace_bytes: false,
+            inter_byte_timeout: None,
+            clear_before_write: false,
+            strict_writes: false,
+            clear_input_before_exchange: true,
         }
     }
 }
@@ -60,218 +292,2577 @@ impl SerialConfig {
         }
     }
 
+    /// create config from `"8N1"`-style framing shorthand
+    ///
+    /// data bits 5-8, parity `N`/`E`/`O` (either case), stop bits 1 or 2
+    pub fn framing(baud_rate: u32, spec: &str) -> Result<Self> {
+        let invalid = || BitcoreError::InvalidParameter {
+            param: "framing".to_string(),
+            reason: format!("expected data bits, parity and stop bits like \"8N1\", got {spec:?}"),
+        };
+
+        let &[data_bits, parity, stop_bits] = spec.as_bytes() else {
+            return Err(invalid());
+        };
+        let data_bits = match data_bits {
+            b'5' => DataBits::Five,
+            b'6' => DataBits::Six,
+            b'7' => DataBits::Seven,
+            b'8' => DataBits::Eight,
+            _ => return Err(invalid()),
+        };
+        let parity = match parity.to_ascii_uppercase() {
+            b'N' => Parity::None,
+            b'E' => Parity::Even,
+            b'O' => Parity::Odd,
+            _ => return Err(invalid()),
+        };
+        let stop_bits = match stop_bits {
+            b'1' => StopBits::One,
+            b'2' => StopBits::Two,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Self::new(baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits))
+    }
+
     /// set timeout for operations
+    ///
+    /// `Duration::MAX` waits forever (see `blocking`)
     pub fn timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    /// never time out: reads wait until data arrives or the port fails
+    ///
+    /// the wait still sleeps `poll_interval` between checks rather than spinning
+    pub fn blocking(self) -> Self {
+        self.timeout(Duration::MAX)
+    }
+
     /// set number of retry attempts
+    ///
+    /// also updates `max_attempts` of a retry policy set with `retry_config`
     pub fn retries(mut self, retries: usize) -> Self {
         self.retries = retries;
+        if let Some(retry) = self.retry.as_mut() {
+            retry.max_attempts = retries;
+        }
+        self
+    }
+
+    /// set the attempts and backoff used when a read or write fails
+    pub fn retry_config(mut self, retry: RetryConfig) -> Self {
+        self.retries = retry.max_attempts;
+        self.retry = Some(retry);
+        self
+    }
+
+    /// the retry policy writes use
+    pub fn retry_policy(&self) -> RetryConfig {
+        self.retry.unwrap_or_else(|| RetryConfig::new(self.retries))
+    }
+
+    /// set number of data bits per character
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    /// set parity checking
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    /// set number of stop bits
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    /// set flow control
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// set how string reads decode incoming bytes
+    pub fn text_decoding(mut self, text_decoding: TextDecoding) -> Self {
+        self.text_decoding = text_decoding;
+        self
+    }
+
+    /// set the terminator `write_line` appends (default `CrLf`)
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
         self
     }
+
+    /// append every read and write to `path` for later replay
+    ///
+    /// see the `capture` module for the file format
+    pub fn capture(mut self, path: Option<PathBuf>) -> Self {
+        self.capture = path;
+        self
+    }
+
+    /// limit writes to at most this many bytes per second
+    pub fn write_rate_limit(mut self, bytes_per_sec: Option<u32>) -> Self {
+        self.write_rate_limit = bytes_per_sec;
+        self
+    }
+
+    /// limit reads to at most this many bytes per second (emulates a slow consumer)
+    pub fn read_rate_limit(mut self, bytes_per_sec: Option<u32>) -> Self {
+        self.read_rate_limit = bytes_per_sec;
+        self
+    }
+
+    /// wait this long after opening before the port is used
+    ///
+    /// usb-cdc devices and boards that reset on open (arduino) ignore anything
+    /// sent before they have booted
+    pub fn open_settle_delay(mut self, delay: Duration) -> Self {
+        self.open_settle_delay = delay;
+        self
+    }
+
+    /// give up with `Timeout` if opening the port takes longer than `timeout`
+    ///
+    /// a port held by another process or a wedged usb adapter can block the
+    /// open indefinitely. the open then runs on a helper thread; if it
+    /// finishes after the timeout the late port is closed again.
+    pub fn open_timeout(mut self, timeout: Duration) -> Self {
+        self.open_timeout = Some(timeout);
+        self
+    }
+
+    /// whether to lock the port against other openers (default true)
+    ///
+    /// an exclusive open of a port that another process holds fails with
+    /// `PortBusy`. turn it off to share a tty on purpose, e.g. with a sniffer.
+    /// only unix ports can be opened shared; elsewhere this has no effect.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// number of bits on the wire per character: start, data, parity and stop bits
+    pub fn bits_per_frame(&self) -> u32 {
+        let data_bits = match self.data_bits {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        };
+        let parity_bits = match self.parity {
+            Parity::None => 0,
+            Parity::Odd | Parity::Even => 1,
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        1 + data_bits + parity_bits + stop_bits
+    }
+
+    /// upper bound on payload throughput for this baud rate and framing
+    pub fn theoretical_bytes_per_sec(&self) -> f64 {
+        f64::from(self.baud_rate) / f64::from(self.bits_per_frame())
+    }
+
+    /// send `payload` whenever nothing has been written for `interval`
+    ///
+    /// keeps idle sessions alive on devices that drop quiet links
+    pub fn heartbeat(mut self, heartbeat: Option<(Vec<u8>, Duration)>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    /// close the port once no read or write has happened for this long
+    ///
+    /// heartbeats do not count as activity. later operations fail with
+    /// `NotConnected`.
+    pub fn idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// flush after every write so the device sees it without os buffering delay
+    ///
+    /// each write then blocks until its bytes have left the output buffer,
+    /// trading throughput for latency
+    pub fn auto_flush(mut self, auto_flush: bool) -> Self {
+        self.auto_flush = auto_flush;
+        self
+    }
+
+    /// extend the timeout of fixed-length reads by their transmission time
+    ///
+    /// see `effective_timeout`; keeps the same timeout working across baud rates
+    pub fn adaptive_timeout(mut self, adaptive_timeout: bool) -> Self {
+        self.adaptive_timeout = adaptive_timeout;
+        self
+    }
+
+    /// reopen the port when a read or write finds the device gone
+    ///
+    /// covers brief usb disconnects: the failed operation is retried once on
+    /// the reopened port. only applies to connections opened by port name.
+    pub fn reconnect(mut self, reconnect: bool) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    /// how often `reconnect` tries to reopen the port before giving up
+    pub fn max_reconnect_attempts(mut self, attempts: usize) -> Self {
+        self.max_reconnect_attempts = attempts;
+        self
+    }
+
+    /// how often reads check the port for new data (default 10ms)
+    ///
+    /// shorter intervals cut latency on fast devices at the cost of cpu time.
+    /// a read can overshoot its timeout by up to one interval, so the interval
+    /// is clamped to the timeout (and to no less than 1ms) when the port opens.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// toggle RTS around every write for an rs-485 transceiver
+    ///
+    /// writes then also wait for the output to drain, like `auto_flush`
+    pub fn rs485(mut self, rs485: Rs485Config) -> Self {
+        self.rs485 = rs485;
+        self
+    }
+
+    /// log a hex + ascii dump of every buffer read or written
+    ///
+    /// dumps go out at `trace` level; when that level is disabled nothing is
+    /// formatted
+    pub fn trace_bytes(mut self, trace_bytes: bool) -> Self {
+        self.trace_bytes = trace_bytes;
+        self
+    }
+
+    /// silence that ends a burst in `read_until_idle`
+    ///
+    /// unlike `timeout`, which bounds the whole read, this is the longest
+    /// allowed gap between two bytes of the same message. not to be confused
+    /// with `idle_timeout`, which closes an unused port.
+    pub fn inter_byte_timeout(mut self, gap: Option<Duration>) -> Self {
+        self.inter_byte_timeout = gap;
+        self
+    }
+
+    /// discard untransmitted output before each write
+    ///
+    /// drops the remains of an earlier write that failed or timed out, so a
+    /// half-sent command cannot run into the next one. `write_all` and the
+    /// frame writers clear once per call, not between their chunks.
+    pub fn clear_before_write(mut self, clear: bool) -> Self {
+        self.clear_before_write = clear;
+        self
+    }
+
+    /// make `write` send all of `data` instead of returning after one
+    /// partial write
+    ///
+    /// a port that accepts only part of a buffer (a full driver queue) is
+    /// written to again for the rest; a write that accepts nothing is an
+    /// error for the retry policy. off by default, where a short write only
+    /// logs a warning and its count is returned as before.
+    pub fn strict_writes(mut self, strict: bool) -> Self {
+        self.strict_writes = strict;
+        self
+    }
+
+    /// whether `transaction` and `write_read_exact` discard pending input
+    /// before sending their request (default true)
+    ///
+    /// turn it off when the reply may legitimately have started arriving
+    /// already, e.g. a device that streams and is only polled for a frame.
+    /// bytes an earlier line or delimiter read took past its end then start
+    /// the reply, ahead of anything still on the port
+    pub fn clear_input_before_exchange(mut self, clear: bool) -> Self {
+        self.clear_input_before_exchange = clear;
+        self
+    }
+
+    /// `poll_interval` clamped to `timeout`, but never a busy loop
+    fn clamped_poll_interval(&self) -> Duration {
+        self.poll_interval
+            .min(self.timeout)
+            .max(Duration::from_millis(1))
+    }
+
+    /// check for settings that cannot work, before any port is opened
+    ///
+    /// a zero baud rate is an `InvalidParameter` error. a timeout shorter than
+    /// `poll_interval` is allowed but logged as a warning: reads then wait
+    /// at most one poll, so they tend to time out before data shows up.
+    pub fn validate(&self) -> Result<()> {
+        self.check()?;
+        if self.timeout < self.poll_interval {
+            warn!(
+                "timeout {:?} is shorter than the poll interval {:?}; \
+                 reads may time out before data is seen",
+                self.timeout, self.poll_interval
+            );
+        }
+        Ok(())
+    }
+
+    /// the errors of `validate`, without its warnings
+    fn check(&self) -> Result<()> {
+        if self.baud_rate == 0 {
+            return Err(BitcoreError::InvalidParameter {
+                param: "baud_rate".to_string(),
+                reason: "must be greater than zero".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// how long `bytes` take on the wire at this baud rate and framing
+    ///
+    /// zero when the baud rate is zero (not validated), rather than dividing by it
+    pub(crate) fn wire_time(&self, bytes: f64) -> Duration {
+        let rate = self.theoretical_bytes_per_sec();
+        if rate > 0.0 {
+            Duration::try_from_secs_f64(bytes / rate).unwrap_or(Duration::MAX)
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// timeout for a read or write of `expected_bytes`
+    ///
+    /// the configured timeout, plus the time those bytes take on the wire
+    /// (`expected_bytes * bits_per_frame / baud_rate`) when `adaptive_timeout`
+    /// is enabled
+    pub fn effective_timeout(&self, expected_bytes: usize) -> Duration {
+        if !self.adaptive_timeout {
+            return self.timeout;
+        }
+        self.timeout
+            .saturating_add(self.wire_time(expected_bytes as f64))
+    }
 }
 
 impl Serial {
     /// create a new serial connection
+    ///
+    /// uses the global default config if one has been set, otherwise
+    /// `SerialConfig::default()`
     pub fn new<P: AsRef<str>>(port: P) -> Result<Self> {
-        Self::with_config(port, &SerialConfig::default())
+        Self::with_config(port, &Self::global_default_config())
     }
 
-    /// create a serial connection with custom configuration
-    pub fn with_config<P: AsRef<str>>(port: P, config: &SerialConfig) -> Result<Self> {
-        let port_builder = serialport::new(port.as_ref(), config.baud_rate)
-            .data_bits(config.data_bits)
-            .parity(config.parity)
-            .stop_bits(config.stop_bits)
-            .flow_control(config.flow_control)
-            .timeout(config.timeout);
-
-        let connection = SerialConnection::connect(port_builder)
-            .map_err(|e| BitcoreError::SerialPort(e.into()))?;
-
-        info!("connected to serial port: {}", port.as_ref());
-
-        Ok(Self {
-            connection: Arc::new(Mutex::new(Some(connection))),
-            timeout: config.timeout,
-            retries: config.retries,
-        })
+    /// set the config `Serial::new` uses from now on
+    ///
+    /// this is process-wide state: it affects every later `Serial::new` call
+    /// from any thread, including ones in libraries. connections that are
+    /// already open and `with_config` calls are unaffected.
+    pub fn set_global_default_config(config: SerialConfig) {
+        // the slot only ever holds a complete config, so a poisoned lock is still usable
+        *GLOBAL_DEFAULT_CONFIG
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Some(config);
     }
 
-    /// list available serial ports
-    pub fn list_ports() -> Result<Vec<SerialPortInfo>> {
-        SerialConnection::list().map_err(BitcoreError::Io)
+    /// go back to `SerialConfig::default()` for `Serial::new`
+    pub fn clear_global_default_config() {
+        *GLOBAL_DEFAULT_CONFIG
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = None;
     }
 
-    /// write data to the serial port
-    pub fn write(&self, data: &[u8]) -> Result<usize> {
-        if data.is_empty() {
-            return Ok(0);
+    /// the config `Serial::new` would use right now
+    pub fn global_default_config() -> SerialConfig {
+        GLOBAL_DEFAULT_CONFIG
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .unwrap_or_default()
+    }
+
+    /// create a serial connection with custom configuration
+    ///
+    /// the port is neither flushed nor cleared after opening, so whatever a
+    /// device sends the moment it is opened (a boot banner) is kept for the
+    /// first read. `discard_input` drops it where it is unwanted.
+    pub fn with_config<P: AsRef<str>>(port: P, config: &SerialConfig) -> Result<Self> {
+        // fail before opening the port; the warnings come from `from_connection`
+        config.check()?;
+        let connection = open_connection(port.as_ref(), config)?;
+        Self::from_connection(connection, Some(port.as_ref().to_string()), config)
+    }
+
+    /// open the first of `ports` that can be opened, trying them in order
+    ///
+    /// if none opens, the `NoPortAvailable` error lists every port tried and
+    /// why it failed
+    pub fn connect_first_available<P: AsRef<str>>(
+        ports: &[P],
+        config: &SerialConfig,
+    ) -> Result<Self> {
+        let mut attempts = Vec::new();
+        for port in ports {
+            let port = port.as_ref();
+            match Self::with_config(port, config) {
+                Ok(serial) => return Ok(serial),
+                Err(e) => {
+                    debug!("could not open {}: {}", port, e);
+                    attempts.push((port.to_string(), e));
+                }
+            }
         }
+        Err(BitcoreError::NoPortAvailable { attempts })
+    }
 
-        let mut conn_lock = self
-            .connection
-            .lock()
-            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+    /// open the first listed port accepted by `predicate`
+    ///
+    /// e.g. `|port| port.port_name.contains("ttyUSB")`; see
+    /// `connect_first_available` for the error
+    pub fn connect_first_matching(
+        config: &SerialConfig,
+        predicate: impl Fn(&SerialPortInfo) -> bool,
+    ) -> Result<Self> {
+        let candidates: Vec<String> = Self::list_ports()?
+            .into_iter()
+            .filter(|port| predicate(port))
+            .map(|port| port.port_name)
+            .collect();
+        Self::connect_first_available(&candidates, config)
+    }
 
-        match conn_lock.as_mut() {
-            Some(conn) => {
-                let mut attempts = 0;
-                loop {
-                    match conn.write(data) {
-                        Ok(size) => {
-                            debug!("wrote {} bytes", size);
-                            return Ok(size);
-                        }
-                        Err(e) if attempts < self.retries => {
-                            warn!("write attempt {} failed: {}", attempts + 1, e);
-                            attempts += 1;
-                            std::thread::sleep(Duration::from_millis(10));
-                        }
-                        Err(e) => {
-                            return Err(BitcoreError::Io(e));
-                        }
-                    }
+    /// find the port whose device answers `request` with `expected_prefix`
+    ///
+    /// opens each of `ports` in turn and probes it, waiting at most 200ms
+    /// (or `config.timeout`, if shorter) for the reply, so a silent port does
+    /// not hold up the scan for the full timeout; non-matching ports are
+    /// closed again. the returned connection uses `config` as given. if none
+    /// matches, the `NoPortAvailable` error says what each port did
+    /// (`ProbeMismatch` for a port that opened but did not answer).
+    pub fn detect_device<P: AsRef<str>>(
+        ports: &[P],
+        request: &[u8],
+        expected_prefix: &[u8],
+        config: &SerialConfig,
+    ) -> Result<Self> {
+        let probe_timeout = config.timeout.min(PROBE_TIMEOUT);
+        let mut attempts = Vec::new();
+        for port in ports {
+            let port = port.as_ref();
+            let outcome = Self::with_config(port, config).and_then(|serial| {
+                if serial.probe_within(request, expected_prefix, probe_timeout)? {
+                    Ok(serial)
+                } else {
+                    Err(BitcoreError::ProbeMismatch {
+                        port: port.to_string(),
+                    })
+                }
+            });
+            match outcome {
+                Ok(serial) => {
+                    info!("detected device on {}", port);
+                    return Ok(serial);
+                }
+                Err(e) => {
+                    debug!("no device on {}: {}", port, e);
+                    attempts.push((port.to_string(), e));
                 }
             }
-            None => Err(BitcoreError::NotConnected),
         }
+        Err(BitcoreError::NoPortAvailable { attempts })
     }
 
-    /// read data from the serial port
-    pub fn read(&self, buffer: &mut [u8]) -> Result<usize> {
-        if buffer.is_empty() {
-            return Ok(0);
+    /// open a port, retrying transient failures such as a busy port
+    ///
+    /// permanent failures (permission denied, invalid parameters) are returned
+    /// immediately; see `BitcoreError::is_transient_open_error`
+    pub fn connect_with_retry<P: AsRef<str>>(
+        port: P,
+        config: &SerialConfig,
+        retry: &RetryConfig,
+    ) -> Result<Self> {
+        Self::connect_with_retry_if(port, config, retry, BitcoreError::is_transient_open_error)
+    }
+
+    /// open a port, retrying failures accepted by `should_retry`
+    pub fn connect_with_retry_if<P: AsRef<str>>(
+        port: P,
+        config: &SerialConfig,
+        retry: &RetryConfig,
+        should_retry: impl Fn(&BitcoreError) -> bool,
+    ) -> Result<Self> {
+        retry.retry(should_retry, || Self::with_config(port.as_ref(), config))
+    }
+
+    /// connect to a serial port shared over tcp (ser2net, terminal servers)
+    ///
+    /// `addr` is `host:port` or `tcp://host:port`. reads and writes behave as
+    /// on a local port; line settings belong to the server, so the ones in
+    /// `config` are ignored and control-line methods fail as unsupported
+    pub fn connect_tcp(addr: &str, config: &SerialConfig) -> Result<Self> {
+        let transport = TcpTransport::connect(addr)?;
+        info!("connected to tcp serial server: {}", addr);
+        Self::from_transport(Box::new(transport), config)
+    }
+
+    /// wrap an already opened port (e.g. a `mock::MockSerial` in tests)
+    ///
+    /// the port's own settings are left as they are; only the timeout and the
+    /// behavior options from `config` are applied
+    pub fn from_port(port: Box<dyn SerialPort>, config: &SerialConfig) -> Result<Self> {
+        Self::from_transport(Box::new(port), config)
+    }
+
+    /// wrap any `Transport`, such as a socket or a custom test double
+    ///
+    /// line settings in `config` are not applied; the transport keeps
+    /// whatever it was set up with. reconnecting is not possible without a
+    /// port path.
+    pub fn from_transport(
+        mut transport: Box<dyn Transport>,
+        config: &SerialConfig,
+    ) -> Result<Self> {
+        transport.set_timeout(config.timeout)?;
+        let mut connection = SerialConnection::from_transport(transport)
+            .with_poll_interval(config.clamped_poll_interval())
+            .with_trace_bytes(config.trace_bytes);
+        init_rs485(&mut connection, config)?;
+        let connection = attach_capture(connection, config)?;
+        Self::from_connection(connection, None, config)
+    }
+
+    /// in-memory connection where everything written can be read back
+    ///
+    /// useful as a smoke test of the read/write path without hardware
+    pub fn loopback() -> Self {
+        let port = Box::new(MockSerial::loopback());
+        Self::from_connection(SerialConnection::new(port), None, &SerialConfig::default())
+            .expect("the default config is valid")
+    }
+
+    /// every constructor ends here, so each one validates `config`
+    fn from_connection(
+        connection: SerialConnection,
+        path: Option<String>,
+        config: &SerialConfig,
+    ) -> Result<Self> {
+        config.validate()?;
+        let serial = Self {
+            connection: Arc::new(ConnectionSlot(Mutex::new(Some(connection)))),
+            path: Arc::new(Mutex::new(path)),
+            config: Arc::new(RwLock::new(config.clone())),
+            last_write: Arc::new(Mutex::new(None)),
+            last_activity: Arc::new(Mutex::new(Instant::now())),
+            write_limiter: config
+                .write_rate_limit
+                .map(|rate| Arc::new(Mutex::new(RateLimiter::new(rate)))),
+            read_limiter: config
+                .read_rate_limit
+                .map(|rate| Arc::new(Mutex::new(RateLimiter::new(rate)))),
+            write_filter: Arc::new(RwLock::new(None)),
+            read_filter: Arc::new(RwLock::new(None)),
+            pending_rx: Arc::new(Mutex::new(VecDeque::new())),
+            last_error: Arc::new(Mutex::new(None)),
+            stats: Arc::new(StatsCounters::default()),
+        };
+
+        if let Some((payload, interval)) = &config.heartbeat {
+            serial.spawn_heartbeat(payload.clone(), *interval);
+        }
+        if let Some(idle_timeout) = config.idle_timeout {
+            serial.spawn_idle_watchdog(idle_timeout);
         }
 
-        let mut conn_lock = self
-            .connection
-            .lock()
-            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        Ok(serial)
+    }
 
-        match conn_lock.as_mut() {
-            Some(conn) => {
-                // set timeout
-                if let Err(e) = conn.set_timeout(self.timeout) {
-                    warn!("failed to set timeout: {}", e);
-                }
+    /// start the background heartbeat writer
+    ///
+    /// the thread only holds weak references and exits once the `Serial` is
+    /// dropped; while disconnected it just waits for `reconnect`
+    fn spawn_heartbeat(&self, payload: Vec<u8>, interval: Duration) {
+        let connection = Arc::downgrade(&self.connection);
+        let last_write = Arc::downgrade(&self.last_write);
+        let check_interval = interval.min(Duration::from_millis(HEARTBEAT_CHECK_INTERVAL_MS));
 
-                match conn.read(buffer) {
-                    Ok(bytes_read) => {
-                        debug!("read {} bytes", bytes_read);
-                        Ok(bytes_read)
-                    }
-                    Err(e) => Err(e.into()),
+        std::thread::spawn(move || {
+            let started = Instant::now();
+            loop {
+                std::thread::sleep(check_interval);
+                if !heartbeat_tick(&connection, &last_write, &payload, interval, started) {
+                    debug!("heartbeat stopped");
+                    return;
                 }
             }
-            None => Err(BitcoreError::NotConnected),
-        }
+        });
     }
 
-    /// read exact number of bytes (blocks until complete or timeout)
-    pub fn read_exact(&self, buffer: &mut [u8]) -> Result<()> {
-        let mut total_read = 0;
-        let start_time = std::time::Instant::now();
+    /// start the background watchdog that closes an idle connection
+    ///
+    /// like the heartbeat it only holds weak references, exits once the
+    /// `Serial` is dropped and idles while disconnected
+    fn spawn_idle_watchdog(&self, idle_timeout: Duration) {
+        let connection = Arc::downgrade(&self.connection);
+        let last_activity = Arc::downgrade(&self.last_activity);
+        let check_interval = idle_timeout.min(Duration::from_millis(HEARTBEAT_CHECK_INTERVAL_MS));
 
-        while total_read < buffer.len() && start_time.elapsed() < self.timeout {
-            match self.read(&mut buffer[total_read..]) {
-                Ok(0) => {
-                    // no data available, continue
-                    std::thread::sleep(Duration::from_millis(1));
-                }
-                Ok(bytes_read) => {
-                    total_read += bytes_read;
-                }
-                Err(e) => return Err(e),
+        std::thread::spawn(move || loop {
+            std::thread::sleep(check_interval);
+            if !idle_tick(&connection, &last_activity, idle_timeout) {
+                debug!("idle watchdog stopped");
+                return;
             }
-        }
+        });
+    }
 
-        if total_read == buffer.len() {
-            Ok(())
-        } else {
-            Err(BitcoreError::Timeout {
-                timeout_ms: self.timeout.as_millis().min(u64::MAX as u128) as u64,
-            })
+    /// note a successful user read or write
+    ///
+    /// resets the idle watchdog and clears any recorded fatal error
+    fn mark_activity(&self) {
+        if let Ok(mut last_activity) = self.last_activity.lock() {
+            *last_activity = Instant::now();
         }
+        self.clear_error();
     }
 
-    /// write string data
-    pub fn write_str(&self, data: &str) -> Result<usize> {
-        self.write(data.as_bytes())
+    /// run `op`, and if it fails because the device went away, reopen the
+    /// port (when configured to) and run it once more
+    fn with_reconnect<T>(&self, mut op: impl FnMut() -> Result<T>) -> Result<T> {
+        match op() {
+            Err(e) if e.is_fatal() && self.settings().reconnect => {
+                if self.try_reconnect() {
+                    op()
+                } else {
+                    Err(e)
+                }
+            }
+            result => result,
+        }
     }
 
-    /// read into a string (until newline or timeout)
-    pub fn read_line(&self) -> Result<String> {
-        let mut line = String::new();
-        let mut buffer = [0u8; 1];
-        let start_time = std::time::Instant::now();
+    /// reopen the stored port path, up to `max_reconnect_attempts` times
+    ///
+    /// waits between attempts according to the retry policy. the connection
+    /// lock is only taken to drop the dead handle and to install the new one,
+    /// so other callers see `NotConnected` meanwhile instead of blocking for
+    /// the whole backoff
+    fn try_reconnect(&self) -> bool {
+        let Some(path) = self.path.lock().ok().and_then(|path| path.clone()) else {
+            // built from a port object, nothing to reopen
+            return false;
+        };
+        let config = self.settings().clone();
+        let policy = config.retry_policy();
 
-        while start_time.elapsed() < self.timeout {
-            match self.read(&mut buffer) {
-                Ok(1) => {
-                    let ch = buffer[0] as char;
-                    if ch == '\n' {
-                        break;
-                    }
-                    if ch != '\r' {
-                        line.push(ch);
+        // the old handle is dead; close it without flushing
+        match self.connection.lock() {
+            Ok(mut conn_lock) => drop(conn_lock.take()),
+            Err(_) => return false,
+        }
+
+        for attempt in 0..config.max_reconnect_attempts {
+            match open_connection(&path, &config) {
+                Ok(connection) => {
+                    let Ok(mut conn_lock) = self.connection.lock() else {
+                        return false;
+                    };
+                    // another caller may have reconnected in the meantime
+                    if conn_lock.is_none() {
+                        *conn_lock = Some(connection);
                     }
+                    self.clear_error();
+                    info!("reconnected to {} after {} attempts", path, attempt + 1);
+                    return true;
                 }
-                Ok(0) => {
-                    std::thread::sleep(Duration::from_millis(1));
-                }
-                Ok(_) => {
-                    // shouldn't happen with 1-byte buffer, but handle it
-                    let ch = buffer[0] as char;
-                    if ch == '\n' {
-                        break;
-                    }
-                    if ch != '\r' {
-                        line.push(ch);
-                    }
+                Err(e) => {
+                    warn!(
+                        "reconnect attempt {} to {} failed: {}",
+                        attempt + 1,
+                        path,
+                        e
+                    );
+                    std::thread::sleep(policy.delay_for_attempt(attempt));
                 }
-                Err(e) => return Err(e),
             }
         }
 
-        if line.is_empty() && start_time.elapsed() >= self.timeout {
-            Err(BitcoreError::Timeout {
-                timeout_ms: self.timeout.as_millis().min(u64::MAX as u128) as u64,
-            })
-        } else {
-            Ok(line)
-        }
+        error!("giving up reconnecting to {}", path);
+        false
     }
 
-    /// flush the serial port
-    pub fn flush(&self) -> Result<()> {
-        let mut conn_lock = self
-            .connection
-            .lock()
-            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
-
-        match conn_lock.as_mut() {
-            Some(conn) => conn.flush().map_err(BitcoreError::Io),
-            None => Err(BitcoreError::NotConnected),
+    /// count timeouts and remember `err` if it leaves the port unusable,
+    /// then hand it back
+    fn record_error(&self, err: BitcoreError) -> BitcoreError {
+        if matches!(err, BitcoreError::Timeout { .. }) {
+            self.stats.record_timeout();
+        }
+        if err.is_fatal() {
+            if let Ok(mut last_error) = self.last_error.lock() {
+                *last_error = Some(err.clone());
+            }
         }
+        err
     }
 
-    /// get port name
-    pub fn port_name(&self) -> Option<String> {
-        let conn_lock = self.connection.lock().ok()?;
-        conn_lock.as_ref()?.name()
+    /// bytes and operations since the port was opened, shared by all clones
+    pub fn stats(&self) -> SerialStats {
+        self.stats.snapshot()
     }
 
-    /// check if connected
-    pub fn is_connected(&self) -> bool {
-        self.connection
-            .lock()
-            .map(|conn| conn.is_some())
-            .unwrap_or(false)
+    /// list available serial ports
+    pub fn list_ports() -> Result<Vec<SerialPortInfo>> {
+        SerialConnection::list().map_err(BitcoreError::Io)
     }
 
+    /// list available serial ports as flat `PortDescriptor`s
+    ///
+    /// the same ports as `list_ports`, with the usb details pulled out of
+    /// serialport's `SerialPortType`
+    pub fn list_ports_described() -> Result<Vec<PortDescriptor>> {
+        Ok(Self::list_ports()?
+            .into_iter()
+            .map(PortDescriptor::from)
+            .collect())
+    }
+
+    /// list available serial ports whose name matches a glob such as `/dev/ttyUSB*`
+    pub fn find_ports_matching(pattern: &str) -> Result<Vec<SerialPortInfo>> {
+        Ok(ports::filter_by_glob(Self::list_ports()?, pattern))
+    }
+
+    /// list available USB serial ports with vendor id `vid` and optionally product id `pid`
+    pub fn find_ports_by_usb(vid: u16, pid: Option<u16>) -> Result<Vec<SerialPortInfo>> {
+        Ok(ports::filter_by_usb(Self::list_ports()?, vid, pid))
+    }
+
+    /// like `find_ports_matching`, but only keep ports that can currently be opened
+    ///
+    /// each candidate is briefly opened and closed again, so ports held by another
+    /// process are skipped
+    pub fn find_openable_ports_matching(pattern: &str) -> Result<Vec<SerialPortInfo>> {
+        Ok(Self::find_ports_matching(pattern)?
+            .into_iter()
+            .filter(|port| {
+                let openable = serialport::new(&port.port_name, 9600).open().is_ok();
+                if !openable {
+                    debug!("skipping port {} (cannot be opened)", port.port_name);
+                }
+                openable
+            })
+            .collect())
+    }
+
+    /// write data to the serial port
+    ///
+    /// with `SerialConfig::reconnect`, a write that finds the device gone
+    /// reopens the port and is tried once more. like `io::Write::write` it
+    /// may send only part of `data` and say so in its count; `write_all` or
+    /// `SerialConfig::strict_writes` send everything
+    pub fn write(&self, data: &[u8]) -> Result<usize> {
+        self.write_single(data, None)
+    }
+
+    /// one `write` call, first waiting out `min_gap` since the last write
+    fn write_single(&self, data: &[u8], min_gap: Option<Duration>) -> Result<usize> {
+        let written = self.with_reconnect(|| self.write_once(data, true, min_gap))?;
+        // write_all resumes by itself; a bare write leaves that to the caller
+        if written < data.len() {
+            warn!(
+                "short write: {} of {} bytes sent, the rest is up to the caller \
+                 (see write_all or SerialConfig::strict_writes)",
+                written,
+                data.len()
+            );
+        }
+        Ok(written)
+    }
+
+    /// `write`, but `clear_before_write` only applies when `first` is set,
+    /// so a multi-call transfer does not discard its own earlier chunks
+    fn write_chunk(&self, data: &[u8], first: bool) -> Result<usize> {
+        self.with_reconnect(|| self.write_once(data, first, None))
+    }
+
+    /// one attempt at writing `data`
+    ///
+    /// with `min_gap`, waits until that long has passed since the last write.
+    /// the wait happens outside the locks; the gap is checked again once they
+    /// are retaken and they stay held until the write is recorded, so
+    /// concurrent spaced writers cannot both pass it
+    fn write_once(&self, data: &[u8], first: bool, min_gap: Option<Duration>) -> Result<usize> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+
+        throttle(self.write_limiter.as_ref(), data.len())?;
+        let (policy, rs485, timeout, clear_output, strict) = {
+            let config = self.settings();
+            (
+                config.retry_policy(),
+                config.rs485,
+                config.timeout,
+                first && config.clear_before_write,
+                config.strict_writes,
+            )
+        };
+
+        let (mut conn_lock, mut last_write) = loop {
+            let conn_lock = self
+                .connection
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+            if conn_lock.is_none() {
+                return Err(BitcoreError::NotConnected);
+            }
+            // taken after the connection lock, as the heartbeat does
+            let last_write = self
+                .last_write
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let remaining = match (min_gap, *last_write) {
+                (Some(min_gap), Some(previous)) => min_gap.saturating_sub(previous.elapsed()),
+                _ => Duration::ZERO,
+            };
+            if remaining.is_zero() {
+                break (conn_lock, last_write);
+            }
+            // readers, the heartbeat and other writers get in meanwhile
+            drop(last_write);
+            drop(conn_lock);
+            debug!("waiting {:?} before spaced write", remaining);
+            std::thread::sleep(remaining);
+        };
+
+        let filtered = current_filter(&self.write_filter)?.map(|filter| filter(data));
+        let payload = filtered.as_deref().unwrap_or(data);
+
+        match conn_lock.as_mut() {
+            Some(_) if payload.is_empty() => Ok(data.len()),
+            Some(conn) => {
+                if clear_output {
+                    conn.clear(ClearBuffer::Output)?;
+                }
+                let mut written = 0;
+                let size = rs485_transmit(conn, &rs485, timeout, |conn| {
+                    // the caller cannot resume part of a transformed buffer,
+                    // so filtered data is always sent whole
+                    let whole = strict || filtered.is_some();
+                    policy.retry(policy.should_retry, || loop {
+                        let size = match conn.write(&payload[written..]) {
+                            Ok(0) if whole => {
+                                return Err(BitcoreError::Io(std::io::Error::from(
+                                    std::io::ErrorKind::WriteZero,
+                                )))
+                            }
+                            Ok(size) => size,
+                            Err(e) => return Err(port_error(conn, "write", e)),
+                        };
+                        written += size;
+                        if !whole || written >= payload.len() {
+                            return Ok(written);
+                        }
+                        debug!("partial write, {} of {} bytes", written, payload.len());
+                    })
+                })
+                .map_err(|e| self.record_error(e))?;
+
+                debug!("wrote {} bytes", size);
+                self.stats.record_write(written);
+                // rs-485 mode has drained already
+                if self.settings().auto_flush && !rs485.enabled {
+                    // still under the connection lock, so the flush
+                    // covers exactly this write
+                    drain_output(conn, timeout)?;
+                }
+                *last_write = Some(Instant::now());
+                self.mark_activity();
+                Ok(if filtered.is_some() { data.len() } else { size })
+            }
+            None => Err(BitcoreError::NotConnected),
+        }
+    }
+
+    /// pass every write through `filter` before it reaches the port
+    ///
+    /// `write` then reports the length of the original data once the whole
+    /// filtered buffer has been sent. replaces any previous write filter.
+    pub fn set_write_filter(&self, filter: impl Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static) {
+        set_filter(&self.write_filter, Some(Arc::new(filter)));
+    }
+
+    /// pass every chunk read from the port through `filter` before returning it
+    ///
+    /// output that does not fit the caller's buffer is returned by the next
+    /// read. replaces any previous read filter.
+    pub fn set_read_filter(&self, filter: impl Fn(&[u8]) -> Vec<u8> + Send + Sync + 'static) {
+        set_filter(&self.read_filter, Some(Arc::new(filter)));
+    }
+
+    /// remove the read and write filters
+    pub fn clear_filters(&self) {
+        set_filter(&self.write_filter, None);
+        set_filter(&self.read_filter, None);
+    }
+
+    /// write data, waiting until at least `min_gap_since_last` has passed since the previous write
+    ///
+    /// the gap is enforced between whole messages, not bytes (e.g. modbus inter-frame silence)
+    pub fn write_spaced(&self, data: &[u8], min_gap_since_last: Duration) -> Result<usize> {
+        self.write_single(data, Some(min_gap_since_last))
+    }
+
+    /// read data from the serial port
+    pub fn read(&self, buffer: &mut [u8]) -> Result<usize> {
+        let timeout = self.settings().timeout;
+        self.read_for(buffer, timeout)
+    }
+
+    /// `read` with `timeout` instead of the configured timeout, for this call only
+    pub fn read_with_timeout(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.read_for(buffer, timeout)
+    }
+
+    /// read data, waiting at most `timeout` for it to arrive
+    ///
+    /// reconnects like `write` when `SerialConfig::reconnect` is set
+    pub(crate) fn read_for(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.with_reconnect(|| self.read_for_once(buffer, timeout))
+    }
+
+    fn read_for_once(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        {
+            // already counted in the stats when it came off the port
+            let mut pending_rx = self
+                .pending_rx
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+            if !pending_rx.is_empty() {
+                let count = buffer.len().min(pending_rx.len());
+                for (slot, byte) in buffer.iter_mut().zip(pending_rx.drain(..count)) {
+                    *slot = byte;
+                }
+                return Ok(count);
+            }
+        }
+
+        // the lock is only held for one poll at a time, so writers and other
+        // callers get in between polls instead of waiting out the whole timeout
+        let policy = self.settings().retry_policy();
+        let mut failures = 0;
+        let start_time = Instant::now();
+        let deadline = deadline_after(timeout);
+        loop {
+            let (result, waiter) = {
+                let mut conn_lock = self
+                    .connection
+                    .lock()
+                    .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+                let conn = conn_lock.as_mut().ok_or(BitcoreError::NotConnected)?;
+                let result = conn
+                    .try_read(buffer)
+                    .map_err(|e| port_error(conn, "read", e));
+                (result, conn.read_waiter())
+            };
+
+            match result {
+                Ok(0) => {}
+                Ok(bytes_read) => {
+                    debug!("read {} bytes", bytes_read);
+                    self.mark_activity();
+                    throttle(self.read_limiter.as_ref(), bytes_read)?;
+                    return self.apply_read_filter(buffer, bytes_read);
+                }
+                // a port error, not the read running out of time: that is
+                // the `Timeout` below and never retried. the backoff stays
+                // within the read timeout
+                Err(e) => {
+                    if !policy.wait_before_retry(policy.should_retry, failures, &e, Some(deadline))
+                    {
+                        return Err(self.record_error(e));
+                    }
+                    failures += 1;
+                    continue;
+                }
+            }
+
+            let elapsed = start_time.elapsed();
+            if elapsed >= timeout {
+                trace!("read timed out after {:?}", timeout);
+                self.stats.record_timeout();
+                return Err(BitcoreError::timeout(timeout));
+            }
+            // waits outside the lock, so writers are not held up meanwhile
+            waiter.wait(timeout - elapsed);
+        }
+    }
+
+    /// run the first `bytes_read` bytes of `buffer` through the read filter
+    ///
+    /// also counts the received bytes (after filtering) in the stats
+    fn apply_read_filter(&self, buffer: &mut [u8], bytes_read: usize) -> Result<usize> {
+        let Some(filter) = current_filter(&self.read_filter)? else {
+            self.stats.record_read(bytes_read);
+            return Ok(bytes_read);
+        };
+
+        let output = filter(&buffer[..bytes_read]);
+        self.stats.record_read(output.len());
+        let count = output.len().min(buffer.len());
+        buffer[..count].copy_from_slice(&output[..count]);
+        if count < output.len() {
+            self.pending_rx
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?
+                .extend(&output[count..]);
+        }
+        Ok(count)
+    }
+
+    /// put bytes a read took too many of back in front of the input
+    fn unread(&self, data: &[u8]) -> Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut pending_rx = self
+            .pending_rx
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        for &byte in data.iter().rev() {
+            pending_rx.push_front(byte);
+        }
+        Ok(())
+    }
+
+    /// take up to `max` held-back bytes, stopping after `delim` if given
+    ///
+    /// lets the exchanges that read the port directly serve them first
+    fn take_pending_rx(&self, max: usize, delim: Option<u8>) -> Result<Vec<u8>> {
+        let mut pending_rx = self
+            .pending_rx
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        let end = delim
+            .and_then(|delim| pending_rx.iter().position(|&byte| byte == delim))
+            .map_or(pending_rx.len(), |pos| pos + 1);
+        Ok(pending_rx.drain(..end.min(max)).collect())
+    }
+
+    /// drop received bytes held back from earlier reads
+    fn clear_pending_rx(&self) -> Result<()> {
+        self.pending_rx
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?
+            .clear();
+        Ok(())
+    }
+
+    /// read everything that arrives, without knowing its length up front
+    ///
+    /// waits up to the configured timeout for the first bytes, then keeps
+    /// reading until the line stays quiet for one poll interval or the
+    /// timeout runs out. whatever arrived is returned even if the timeout
+    /// cut it short; only receiving nothing at all is a `Timeout`.
+    pub fn read_to_vec(&self) -> Result<Vec<u8>> {
+        let gap = self.settings().clamped_poll_interval();
+        self.read_until_gap(gap)
+    }
+
+    /// read one burst: everything up to the first silence of `inter_byte_timeout`
+    ///
+    /// the usual uart frame-gap detection for protocols without delimiters.
+    /// like `read_to_vec`, but the gap comes from
+    /// `SerialConfig::inter_byte_timeout` (one poll interval if unset)
+    pub fn read_until_idle(&self) -> Result<Vec<u8>> {
+        let gap = {
+            let config = self.settings();
+            config
+                .inter_byte_timeout
+                .unwrap_or_else(|| config.clamped_poll_interval())
+        };
+        self.read_until_gap(gap)
+    }
+
+    /// wait up to the timeout for data, then collect until `gap` passes
+    /// without a new byte
+    fn read_until_gap(&self, gap: Duration) -> Result<Vec<u8>> {
+        let timeout = self.settings().timeout;
+        let deadline = deadline_after(timeout);
+
+        let mut data = Vec::new();
+        let mut chunk = [0u8; READ_CHUNK_LEN];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            let wait = if data.is_empty() {
+                remaining
+            } else {
+                gap.min(remaining)
+            };
+
+            match self.read_for(&mut chunk, wait) {
+                Ok(count) => data.extend_from_slice(&chunk[..count]),
+                Err(BitcoreError::Timeout { .. }) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        if data.is_empty() {
+            return Err(BitcoreError::timeout(timeout));
+        }
+        Ok(data)
+    }
+
+    /// read into a buffer that does not need to be initialized first
+    ///
+    /// behaves like `read` (held-back input first, then waits up to the
+    /// configured timeout), taking at most 256 bytes per call.
+    /// returns the count written; elements past it are left untouched.
+    pub fn read_uninit(&self, buffer: &mut [MaybeUninit<u8>]) -> Result<usize> {
+        // the port only reads into initialized memory, so bytes land in a
+        // small scratch chunk and are copied over; the caller's slots are
+        // never written before there is data for them
+        let mut chunk = [0u8; READ_CHUNK_LEN];
+        let len = buffer.len().min(READ_CHUNK_LEN);
+        let bytes_read = self.read(&mut chunk[..len])?;
+        for (slot, &byte) in buffer.iter_mut().zip(&chunk[..bytes_read]) {
+            slot.write(byte);
+        }
+        Ok(bytes_read)
+    }
+
+    /// read exact number of bytes (blocks until complete or timeout)
+    ///
+    /// with `adaptive_timeout` the budget grows with the buffer length
+    pub fn read_exact(&self, buffer: &mut [u8]) -> Result<()> {
+        let timeout = self.settings().effective_timeout(buffer.len());
+        self.read_exact_deadline(buffer, deadline_after(timeout))
+    }
+
+    /// read exact number of bytes, giving up once `deadline` has passed
+    ///
+    /// a timeout with nothing received is `Timeout`; after a short read it is
+    /// `PartialRead`, with the bytes that did arrive at the front of `buffer`
+    pub fn read_exact_deadline(&self, buffer: &mut [u8], deadline: Instant) -> Result<()> {
+        let budget = deadline.saturating_duration_since(Instant::now());
+        let expected = buffer.len();
+        let mut total_read = 0;
+        let timed_out = |got: usize| match got {
+            0 => BitcoreError::timeout(budget),
+            got => BitcoreError::PartialRead { got, expected },
+        };
+
+        while total_read < buffer.len() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(timed_out(total_read));
+            }
+
+            // read_for blocks for the rest of the budget, waking as input
+            // arrives; 0 only means the read filter dropped what came in
+            match self.read_for(&mut buffer[total_read..], remaining) {
+                Ok(bytes_read) => total_read += bytes_read,
+                Err(BitcoreError::Timeout { .. }) => return Err(timed_out(total_read)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// read until `delim` is received (the delimiter is included in the result),
+    /// giving up once `deadline` has passed
+    pub fn read_until_deadline(&self, delim: u8, deadline: Instant) -> Result<Vec<u8>> {
+        let budget = deadline.saturating_duration_since(Instant::now());
+        match self.collect_until(delim, deadline, usize::MAX)? {
+            (data, true) => Ok(data),
+            (_, false) => Err(BitcoreError::timeout(budget)),
+        }
+    }
+
+    /// read until `delim` is received, within the configured timeout
+    ///
+    /// `include_delim` controls whether the delimiter ends the returned data.
+    /// if the timeout passes after some bytes arrived, they are returned in
+    /// `BitcoreError::DelimiterNotFound`; with nothing received it is a plain
+    /// `Timeout`.
+    pub fn read_until(&self, delim: u8, include_delim: bool) -> Result<Vec<u8>> {
+        let timeout = self.settings().timeout;
+        match self.collect_until(delim, deadline_after(timeout), usize::MAX)? {
+            (mut data, true) => {
+                if !include_delim {
+                    data.pop();
+                }
+                Ok(data)
+            }
+            (data, false) if data.is_empty() => Err(BitcoreError::timeout(timeout)),
+            (partial, false) => Err(BitcoreError::DelimiterNotFound {
+                partial,
+                timeout_ms: timeout.as_millis().min(u64::MAX as u128) as u64,
+            }),
+        }
+    }
+
+    /// clear input, write `request` and read the reply up to and including `delim`
+    ///
+    /// the connection stays locked for the whole exchange, so no other thread
+    /// can write or read in between (half-duplex protocols such as modbus).
+    /// shares the configured timeout between write and read; a reply that
+    /// stops short of `delim` comes back in `DelimiterNotFound`. rate limits
+    /// and read/write filters do not apply inside a transaction.
+    pub fn transaction(&self, request: &[u8], delim: u8) -> Result<Vec<u8>> {
+        let (timeout, rs485, clear_input) = {
+            let config = self.settings();
+            (
+                config.timeout,
+                config.rs485,
+                config.clear_input_before_exchange,
+            )
+        };
+        let deadline = deadline_after(timeout);
+        // without clearing, bytes held back by an earlier read start the reply
+        let held = if clear_input {
+            self.clear_pending_rx()?;
+            Vec::new()
+        } else {
+            self.take_pending_rx(usize::MAX, Some(delim))?
+        };
+        let held_len = held.len();
+
+        let (reply, complete) = self.with_connection(|conn| {
+            if clear_input {
+                conn.clear(ClearBuffer::Input)?;
+            }
+            rs485_transmit(conn, &rs485, timeout, |conn| {
+                conn.write_all(request)
+                    .map_err(|e| port_error(conn, "write", e))
+            })
+            .map_err(|e| self.record_error(e))?;
+
+            let mut reply = held;
+            if reply.last() == Some(&delim) {
+                return Ok((reply, true));
+            }
+            let mut byte = [0u8; 1];
+            loop {
+                match conn.try_read(&mut byte) {
+                    Ok(0) => {}
+                    Ok(_) => {
+                        reply.push(byte[0]);
+                        if byte[0] == delim {
+                            return Ok((reply, true));
+                        }
+                        continue;
+                    }
+                    Err(e) => return Err(self.record_error(port_error(conn, "read", e))),
+                }
+                if Instant::now() >= deadline {
+                    return Ok((reply, false));
+                }
+                std::thread::sleep(conn.poll_interval());
+            }
+        })?;
+
+        if let Ok(mut last_write) = self.last_write.lock() {
+            *last_write = Some(Instant::now());
+        }
+        self.mark_activity();
+        self.stats.record_write(request.len());
+        if reply.len() > held_len {
+            self.stats.record_read(reply.len() - held_len);
+        }
+
+        match (reply, complete) {
+            (reply, true) => Ok(reply),
+            (reply, false) if reply.is_empty() => {
+                Err(self.record_error(BitcoreError::timeout(timeout)))
+            }
+            (partial, false) => Err(BitcoreError::DelimiterNotFound {
+                partial,
+                timeout_ms: timeout.as_millis().min(u64::MAX as u128) as u64,
+            }),
+        }
+    }
+
+    /// write `request` and read exactly `response.len()` reply bytes
+    ///
+    /// the fixed-length sibling of `transaction`: input is cleared first
+    /// (see `clear_input_before_exchange`), the connection stays locked for
+    /// the whole exchange and the configured timeout covers both halves.
+    /// nothing received is `Timeout`, a short reply `PartialRead` with the
+    /// bytes that arrived at the front of `response`.
+    pub fn write_read_exact(&self, request: &[u8], response: &mut [u8]) -> Result<()> {
+        let (timeout, rs485, clear_input) = {
+            let config = self.settings();
+            (
+                config.timeout,
+                config.rs485,
+                config.clear_input_before_exchange,
+            )
+        };
+        let deadline = deadline_after(timeout);
+        let held = if clear_input {
+            self.clear_pending_rx()?;
+            0
+        } else {
+            let held = self.take_pending_rx(response.len(), None)?;
+            response[..held.len()].copy_from_slice(&held);
+            held.len()
+        };
+
+        let got = self.with_connection(|conn| {
+            if clear_input {
+                conn.clear(ClearBuffer::Input)?;
+            }
+            rs485_transmit(conn, &rs485, timeout, |conn| {
+                conn.write_all(request)
+                    .map_err(|e| port_error(conn, "write", e))
+            })
+            .map_err(|e| self.record_error(e))?;
+            read_exact_locked(conn, &mut response[held..], deadline)
+                .map_err(|e| self.record_error(e))
+        })?;
+
+        if let Ok(mut last_write) = self.last_write.lock() {
+            *last_write = Some(Instant::now());
+        }
+        self.mark_activity();
+        self.stats.record_write(request.len());
+        if got > 0 {
+            self.stats.record_read(got);
+        }
+        let got = held + got;
+
+        match got {
+            got if got == response.len() => Ok(()),
+            0 => Err(self.record_error(BitcoreError::timeout(timeout))),
+            got => Err(BitcoreError::PartialRead {
+                got,
+                expected: response.len(),
+            }),
+        }
+    }
+
+    /// read `count` holding registers of `slave` starting at `addr` (function 0x03)
+    ///
+    /// exception replies fail with `ModbusException`, corrupted ones with
+    /// `ChecksumMismatch`, and replies for another slave or function with
+    /// `Deserialize`
+    pub fn modbus_read_holding(&self, slave: u8, addr: u16, count: u16) -> Result<Vec<u16>> {
+        if count == 0 || count > modbus::MAX_READ_REGISTERS {
+            return Err(BitcoreError::InvalidParameter {
+                param: "count".to_string(),
+                reason: format!("must be 1..={}", modbus::MAX_READ_REGISTERS),
+            });
+        }
+
+        let mut data = [0u8; 4];
+        data[..2].copy_from_slice(&addr.to_be_bytes());
+        data[2..].copy_from_slice(&count.to_be_bytes());
+        // slave, function, byte count, registers, crc
+        let reply_len = 5 + 2 * count as usize;
+        let frame =
+            self.modbus_exchange(slave, modbus::READ_HOLDING_REGISTERS, &data, reply_len)?;
+
+        let body = modbus::decode_response(slave, modbus::READ_HOLDING_REGISTERS, &frame)?;
+        if body[0] as usize != 2 * count as usize {
+            return Err(modbus::malformed(format!(
+                "{} data bytes for {count} registers",
+                body[0]
+            )));
+        }
+        Ok(body[1..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect())
+    }
+
+    /// write `value` to holding register `addr` of `slave` (function 0x06)
+    ///
+    /// the slave echoes the request; any other reply is an error as in
+    /// `modbus_read_holding`
+    pub fn modbus_write_single(&self, slave: u8, addr: u16, value: u16) -> Result<()> {
+        let mut data = [0u8; 4];
+        data[..2].copy_from_slice(&addr.to_be_bytes());
+        data[2..].copy_from_slice(&value.to_be_bytes());
+        let frame = self.modbus_exchange(slave, modbus::WRITE_SINGLE_REGISTER, &data, 8)?;
+
+        let body = modbus::decode_response(slave, modbus::WRITE_SINGLE_REGISTER, &frame)?;
+        if body != data {
+            return Err(modbus::malformed(
+                "write echo does not match the request".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// send one rtu request and read a reply of `reply_len` bytes, or a
+    /// shorter exception reply
+    ///
+    /// keeps the connection locked like `transaction`, after waiting out the
+    /// inter-frame silence
+    fn modbus_exchange(
+        &self,
+        slave: u8,
+        function: u8,
+        data: &[u8],
+        reply_len: usize,
+    ) -> Result<Vec<u8>> {
+        let request = modbus::encode_request(slave, function, data);
+        let (silence, timeout, rs485) = {
+            let config = self.settings();
+            (modbus::frame_silence(&config), config.timeout, config.rs485)
+        };
+
+        self.clear_pending_rx()?;
+        let frame = self.with_connection(|conn| {
+            std::thread::sleep(silence);
+            conn.clear(ClearBuffer::Input)?;
+            rs485_transmit(conn, &rs485, timeout, |conn| {
+                conn.write_all(&request)
+                    .map_err(|e| port_error(conn, "write", e))
+            })
+            .map_err(|e| self.record_error(e))?;
+            self.stats.record_write(request.len());
+
+            let deadline = deadline_after(timeout);
+            let mut frame = vec![0u8; reply_len];
+            // the first bytes tell an exception reply from a normal one
+            let mut got =
+                read_exact_locked(conn, &mut frame[..modbus::EXCEPTION_FRAME_LEN], deadline)
+                    .map_err(|e| self.record_error(e))?;
+            if got == modbus::EXCEPTION_FRAME_LEN && frame[1] & 0x80 != 0 {
+                frame.truncate(got);
+            } else if got == modbus::EXCEPTION_FRAME_LEN {
+                got += read_exact_locked(conn, &mut frame[got..], deadline)
+                    .map_err(|e| self.record_error(e))?;
+            }
+            if got > 0 {
+                self.stats.record_read(got);
+            }
+
+            match got {
+                0 => Err(self.record_error(BitcoreError::timeout(timeout))),
+                got if got < frame.len() => Err(BitcoreError::PartialRead {
+                    got,
+                    expected: frame.len(),
+                }),
+                _ => Ok(frame),
+            }
+        })?;
+
+        self.mark_activity();
+        Ok(frame)
+    }
+
+    /// read byte by byte until `delim` (kept), `deadline`, or `limit` bytes
+    /// without a delimiter
+    ///
+    /// returns what was received and whether the delimiter was seen
+    fn collect_until(&self, delim: u8, deadline: Instant, limit: usize) -> Result<(Vec<u8>, bool)> {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; READ_CHUNK_LEN];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || data.len() >= limit {
+                return Ok((data, false));
+            }
+
+            // read what is there in one go and hand back what follows `delim`
+            let want = READ_CHUNK_LEN.min(limit - data.len());
+            match self.read_for(&mut chunk[..want], remaining) {
+                Ok(bytes_read) => {
+                    let received = &chunk[..bytes_read];
+                    if let Some(end) = received.iter().position(|&byte| byte == delim) {
+                        data.extend_from_slice(&received[..=end]);
+                        self.unread(&received[end + 1..])?;
+                        return Ok((data, true));
+                    }
+                    data.extend_from_slice(received);
+                }
+                Err(BitcoreError::Timeout { .. }) => return Ok((data, false)),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// discard input up to and including the next `delim`, or until `deadline`
+    ///
+    /// resyncs after an oversized frame; `limit` only bounds each batch
+    fn skip_past(&self, delim: u8, deadline: Instant, limit: usize) -> Result<()> {
+        loop {
+            let (skipped, found) = self.collect_until(delim, deadline, limit)?;
+            if found || skipped.len() < limit {
+                return Ok(());
+            }
+        }
+    }
+
+    /// send a typed command and decode the typed response with `codec`
+    ///
+    /// runs as a `transaction`: input is cleared, the encoded command is
+    /// written and the reply read up to `codec.delimiter()`, all under one
+    /// lock and one timeout. the delimiter is stripped before `decode`
+    pub fn query<C: Codec>(&self, codec: &C, cmd: &C::Cmd) -> Result<C::Resp> {
+        let delim = codec.delimiter();
+        let mut frame = self.transaction(&codec.encode(cmd), delim)?;
+        frame.pop();
+        codec.decode(&frame)
+    }
+
+    /// write `data` followed by its modbus CRC-16, low byte first
+    pub fn write_with_crc16(&self, data: &[u8]) -> Result<()> {
+        let mut frame = Vec::with_capacity(data.len() + 2);
+        frame.extend_from_slice(data);
+        frame.extend_from_slice(&checksum::crc16_modbus(data).to_le_bytes());
+        self.write_all(&frame)
+    }
+
+    /// read `len` bytes plus a trailing modbus CRC-16 and return the `len` bytes
+    ///
+    /// fails with `ChecksumMismatch` if the CRC does not match
+    pub fn read_exact_with_crc16(&self, len: usize) -> Result<Vec<u8>> {
+        let mut frame = vec![0u8; len + 2];
+        self.read_exact(&mut frame)?;
+
+        let expected = u16::from_le_bytes([frame[len], frame[len + 1]]);
+        frame.truncate(len);
+        let actual = checksum::crc16_modbus(&frame);
+        if expected != actual {
+            return Err(BitcoreError::ChecksumMismatch { expected, actual });
+        }
+        Ok(frame)
+    }
+
+    /// COBS-encode `payload` and write it as one zero-terminated frame
+    pub fn write_frame(&self, payload: &[u8]) -> Result<()> {
+        let mut frame = framing::cobs_encode(payload);
+        frame.push(0);
+        self.write_all(&frame)
+    }
+
+    /// read one zero-terminated COBS frame and return the decoded payload
+    ///
+    /// empty frames (back-to-back delimiters, often sent to resync) are
+    /// skipped. a frame that would decode to more than `max_len` bytes fails
+    /// with `InvalidParameter`; the rest of it, up to and including its
+    /// delimiter, is discarded first (within the timeout), so the next call
+    /// starts at the following frame.
+    pub fn read_frame(&self, max_len: usize) -> Result<Vec<u8>> {
+        let frame =
+            self.read_delimited_frame(0, framing::cobs_max_encoded_len(max_len), max_len)?;
+        framing::cobs_decode(&frame)
+    }
+
+    /// write `payload` behind a `prefix` header giving its length
+    ///
+    /// header and payload go out in one `write_all`; payloads too long for
+    /// the header fail with `InvalidParameter` before anything is sent
+    pub fn write_length_prefixed(&self, prefix: LengthPrefix, payload: &[u8]) -> Result<()> {
+        let mut frame = prefix.encode(payload.len())?;
+        frame.extend_from_slice(payload);
+        self.write_all(&frame)
+    }
+
+    /// read a `prefix` length header and then exactly that many payload bytes
+    ///
+    /// header and payload share one timeout; with `adaptive_timeout` it grows
+    /// with the length the header announces, as in `read_exact`. a header
+    /// announcing more than `max_len` bytes fails with `InvalidParameter`
+    /// without allocating or reading the payload, which is left queued;
+    /// discard input to resync after a corrupt header
+    pub fn read_length_prefixed(&self, prefix: LengthPrefix, max_len: usize) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let header_len = prefix.header_len();
+        let deadline = deadline_after(self.settings().effective_timeout(header_len));
+
+        let mut header = [0u8; 4];
+        let header = &mut header[..header_len];
+        self.read_exact_deadline(header, deadline)?;
+        let len = prefix.decode(header)?;
+        if len > max_len {
+            return Err(BitcoreError::InvalidParameter {
+                param: "frame".to_string(),
+                reason: format!("length prefix announces {len} bytes, limit is {max_len}"),
+            });
+        }
+
+        // the budget for the whole frame, counted from the start
+        let timeout = self.settings().effective_timeout(header_len + len);
+        let deadline = start
+            .checked_add(timeout)
+            .map_or(deadline, |d| d.max(deadline));
+        let mut payload = vec![0u8; len];
+        self.read_exact_deadline(&mut payload, deadline)?;
+        Ok(payload)
+    }
+
+    /// SLIP-encode `payload` and write it as one END-terminated frame
+    pub fn write_slip(&self, payload: &[u8]) -> Result<()> {
+        self.write_all(&slip::slip_encode(payload))
+    }
+
+    /// read one END-terminated SLIP frame and return the unescaped payload
+    ///
+    /// empty frames, such as the END some stacks send first to flush line
+    /// noise, are skipped. frames longer than `max_len` are handled as in
+    /// `read_frame`.
+    pub fn read_slip(&self, max_len: usize) -> Result<Vec<u8>> {
+        // every payload byte may be escaped into two
+        let frame = self.read_delimited_frame(slip::END, max_len.saturating_mul(2), max_len)?;
+        slip::slip_decode(&frame)
+    }
+
+    /// read the next non-empty frame terminated by `delim`, without the delimiter
+    ///
+    /// fails with `InvalidParameter` once more than `max_encoded_len` bytes
+    /// arrive without a delimiter (`max_len` is only used in the message),
+    /// after skipping to the end of that frame
+    fn read_delimited_frame(
+        &self,
+        delim: u8,
+        max_encoded_len: usize,
+        max_len: usize,
+    ) -> Result<Vec<u8>> {
+        let timeout = self.settings().timeout;
+        let deadline = deadline_after(timeout);
+        let limit = max_encoded_len.saturating_add(1);
+
+        loop {
+            match self.collect_until(delim, deadline, limit)? {
+                (frame, true) if frame.len() == 1 => continue,
+                (mut frame, true) => {
+                    frame.pop();
+                    return Ok(frame);
+                }
+                (frame, false) if frame.len() >= limit => {
+                    self.skip_past(delim, deadline, limit)?;
+                    return Err(BitcoreError::InvalidParameter {
+                        param: "frame".to_string(),
+                        reason: format!("frame longer than {max_len} bytes"),
+                    });
+                }
+                (frame, false) if frame.is_empty() => return Err(BitcoreError::timeout(timeout)),
+                (partial, false) => {
+                    return Err(BitcoreError::DelimiterNotFound {
+                        partial,
+                        timeout_ms: timeout.as_millis().min(u64::MAX as u128) as u64,
+                    })
+                }
+            }
+        }
+    }
+
+    /// write the whole buffer
+    ///
+    /// unlike `write`, which may accept only part of `data`, this keeps going
+    /// until everything is written. each chunk gets the retry policy of
+    /// `write`; the whole transfer shares one timeout (see `effective_timeout`).
+    pub fn write_all(&self, data: &[u8]) -> Result<()> {
+        let timeout = self.settings().effective_timeout(data.len());
+        self.write_all_deadline(data, deadline_after(timeout))
+    }
+
+    /// write the whole buffer, giving up once `deadline` has passed
+    pub fn write_all_deadline(&self, data: &[u8], deadline: Instant) -> Result<()> {
+        let budget = deadline.saturating_duration_since(Instant::now());
+        let mut written = 0;
+
+        while written < data.len() {
+            if Instant::now() >= deadline {
+                return Err(BitcoreError::timeout(budget));
+            }
+
+            match self.write_chunk(&data[written..], written == 0)? {
+                0 => std::thread::sleep(Duration::from_millis(1)),
+                size => written += size,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// write string data
+    pub fn write_str(&self, data: &str) -> Result<usize> {
+        self.write(data.as_bytes())
+    }
+
+    /// write formatted text, as `write_all`, so `write!(serial, ...)` works
+    ///
+    /// the text is formatted into one buffer first (a plain literal is not
+    /// copied), so it goes out as a single transfer under one timeout. as with
+    /// `write_all`, another writer can still get in between chunks
+    pub fn write_fmt(&self, args: fmt::Arguments<'_>) -> Result<()> {
+        match args.as_str() {
+            Some(text) => self.write_all(text.as_bytes()),
+            None => self.write_all(fmt::format(args).as_bytes()),
+        }
+    }
+
+    /// write `line` followed by the configured `line_ending`
+    ///
+    /// the whole line is written with `write_all`, so a command is never cut
+    /// off before its terminator. returns the bytes written, terminator included
+    pub fn write_line(&self, line: &str) -> Result<usize> {
+        let line_ending = self.settings().line_ending;
+        let mut data = Vec::with_capacity(line.len() + 2);
+        data.extend_from_slice(line.as_bytes());
+        data.extend_from_slice(line_ending.as_bytes());
+        self.write_all(&data)?;
+        Ok(data.len())
+    }
+
+    /// read into a string (until newline or timeout)
+    ///
+    /// bytes are decoded according to the configured `TextDecoding` policy;
+    /// the default reads bytes as latin-1, so devices that send utf-8 want
+    /// `TextDecoding::Strict` or `Lossy`. lines longer than
+    /// `DEFAULT_MAX_LINE_LEN` fail with `LineTooLong`
+    pub fn read_line(&self) -> Result<String> {
+        self.read_line_limited(DEFAULT_MAX_LINE_LEN)
+    }
+
+    /// `read_line` with `timeout` instead of the configured timeout, for this call only
+    pub fn read_line_with_timeout(&self, timeout: Duration) -> Result<String> {
+        let line = self.read_line_raw(timeout, DEFAULT_MAX_LINE_LEN)?;
+        self.settings().text_decoding.decode(line)
+    }
+
+    /// read one line as raw bytes, without any text decoding
+    ///
+    /// the line ending (`\n`, and any `\r`) is stripped as in `read_line`
+    pub fn read_line_bytes(&self) -> Result<Vec<u8>> {
+        let timeout = self.settings().timeout;
+        self.read_line_raw(timeout, DEFAULT_MAX_LINE_LEN)
+    }
+
+    /// `read_line`, but give up with `LineTooLong` once more than `max_len`
+    /// bytes arrive without a newline
+    ///
+    /// protects against a peer that never ends its line. the bytes read so
+    /// far are dropped; the rest of the overlong line is still queued and
+    /// comes back from the next read.
+    pub fn read_line_limited(&self, max_len: usize) -> Result<String> {
+        let timeout = self.settings().timeout;
+        let line = self.read_line_raw(timeout, max_len)?;
+        self.settings().text_decoding.decode(line)
+    }
+
+    /// iterate over incoming lines, like `BufRead::lines`
+    ///
+    /// timeouts are yielded as errors unless `Lines::skip_timeouts` is used;
+    /// the iteration ends once the port is closed or disconnected
+    pub fn lines(&self) -> Lines<'_> {
+        Lines::new(self)
+    }
+
+    /// serialize `value` as one line of json and write it
+    #[cfg(feature = "serde_json")]
+    pub fn write_json<T: serde::Serialize + ?Sized>(&self, value: &T) -> Result<()> {
+        let mut line = serde_json::to_vec(value).map_err(|e| BitcoreError::InvalidParameter {
+            param: "value".to_string(),
+            reason: e.to_string(),
+        })?;
+        line.push(b'\n');
+        let deadline = deadline_after(self.settings().timeout);
+        self.write_all_deadline(&line, deadline)
+    }
+
+    /// read one line and parse it as json
+    #[cfg(feature = "serde_json")]
+    pub fn read_json<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        let timeout = self.settings().timeout;
+        let line = self.read_line_raw(timeout, DEFAULT_MAX_LINE_LEN)?;
+        serde_json::from_slice(&line).map_err(|e| BitcoreError::Deserialize {
+            reason: e.to_string(),
+        })
+    }
+
+    /// read bytes up to a newline (or `timeout`), without the line ending
+    ///
+    /// reads in chunks and scans them in memory; bytes after the newline are
+    /// handed back for the next read
+    fn read_line_raw(&self, timeout: Duration, max_len: usize) -> Result<Vec<u8>> {
+        let mut line = Vec::new();
+        let mut chunk = [0u8; READ_CHUNK_LEN];
+        let start_time = std::time::Instant::now();
+
+        while start_time.elapsed() < timeout {
+            match self.read_for(&mut chunk, timeout.saturating_sub(start_time.elapsed())) {
+                Ok(bytes_read) => {
+                    let received = &chunk[..bytes_read];
+                    let end = received.iter().position(|&byte| byte == b'\n');
+                    for (i, &byte) in received[..end.unwrap_or(bytes_read)].iter().enumerate() {
+                        if byte == b'\r' {
+                            continue;
+                        }
+                        if line.len() >= max_len {
+                            self.unread(&received[i + 1..])?;
+                            return Err(BitcoreError::LineTooLong { max_len });
+                        }
+                        line.push(byte);
+                    }
+                    if let Some(end) = end {
+                        self.unread(&received[end + 1..])?;
+                        return Ok(line);
+                    }
+                }
+                Err(BitcoreError::Timeout { .. }) => return Err(BitcoreError::timeout(timeout)),
+                Err(e) => return Err(e),
+            }
+        }
+
+        if line.is_empty() && start_time.elapsed() >= timeout {
+            Err(BitcoreError::timeout(timeout))
+        } else {
+            Ok(line)
+        }
+    }
+
+    /// flush the serial port (alias of `transmit_flush`)
+    pub fn flush(&self) -> Result<()> {
+        self.transmit_flush()
+    }
+
+    /// block until every queued byte has left the os output buffer
+    /// (alias of `transmit_flush`)
+    ///
+    /// this busy-waits on `bytes_to_write`, bounded by the configured timeout,
+    /// and holds the port meanwhile. use it before turning an rs-485
+    /// transceiver around so the tail of the frame is not cut off.
+    pub fn drain(&self) -> Result<()> {
+        self.transmit_flush()
+    }
+
+    /// block until everything written has left the output buffer
+    ///
+    /// flushes the port, then polls `bytes_to_write` until it reaches zero,
+    /// bounded by the configured timeout
+    pub fn transmit_flush(&self) -> Result<()> {
+        let timeout = self.settings().timeout;
+        self.with_connection(|conn| drain_output(conn, timeout))
+    }
+
+    /// number of received bytes that a read would return right now
+    ///
+    /// includes bytes already taken from the port and held for the next read
+    /// (read-filter output, the rest of a chunk after a line)
+    pub fn bytes_available(&self) -> Result<usize> {
+        let pending = self
+            .pending_rx
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?
+            .len();
+        let queued = self.with_connection(|conn| Ok(conn.bytes_to_read()?))?;
+        Ok(pending + queued as usize)
+    }
+
+    /// number of written bytes still waiting in the output buffer
+    pub fn bytes_pending_write(&self) -> Result<usize> {
+        self.with_connection(|conn| Ok(conn.bytes_to_write()? as usize))
+    }
+
+    /// drop any received data that has not been read yet (alias of `clear_input`)
+    pub fn discard_input(&self) -> Result<()> {
+        self.clear_input()
+    }
+
+    /// drop any written data that has not been transmitted yet (alias of `clear_output`)
+    pub fn discard_output(&self) -> Result<()> {
+        self.clear_output()
+    }
+
+    /// drop any received data that has not been read yet
+    ///
+    /// use before a request/response exchange so leftovers of an earlier,
+    /// timed-out reply are not read as the answer
+    pub fn clear_input(&self) -> Result<()> {
+        self.clear(ClearBuffer::Input)
+    }
+
+    /// drop any written data that has not been transmitted yet
+    pub fn clear_output(&self) -> Result<()> {
+        self.clear(ClearBuffer::Output)
+    }
+
+    /// drop both pending input and pending output
+    pub fn clear_all(&self) -> Result<()> {
+        self.clear(ClearBuffer::All)
+    }
+
+    fn clear(&self, buffer: ClearBuffer) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.clear(buffer)?))?;
+        if matches!(buffer, ClearBuffer::Input | ClearBuffer::All) {
+            // bytes held back for the next read are received data too
+            self.clear_pending_rx()?;
+        }
+        Ok(())
+    }
+
+    /// record everything received during `duration`
+    ///
+    /// each chunk is tagged with its arrival time relative to the start, which
+    /// keeps the bursts and gaps of the stream visible
+    pub fn sample(&self, duration: Duration) -> Result<Vec<(Duration, Vec<u8>)>> {
+        let started = Instant::now();
+        let deadline = started + duration;
+        let mut chunks = Vec::new();
+        let mut buffer = [0u8; 256];
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            match self.read_for(&mut buffer, remaining) {
+                Ok(0) => {}
+                Ok(bytes_read) => chunks.push((started.elapsed(), buffer[..bytes_read].to_vec())),
+                Err(BitcoreError::Timeout { .. }) => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        debug!("sampled {} chunks over {:?}", chunks.len(), duration);
+        Ok(chunks)
+    }
+
+    /// send `request` and check whether the reply starts with `expected_prefix`
+    ///
+    /// stale input is discarded first. no reply, or too short a reply, within
+    /// the configured timeout is `Ok(false)`, as is a reply that differs.
+    pub fn probe(&self, request: &[u8], expected_prefix: &[u8]) -> Result<bool> {
+        self.probe_within(request, expected_prefix, self.settings().timeout)
+    }
+
+    /// `probe`, waiting at most `timeout` for the reply
+    fn probe_within(
+        &self,
+        request: &[u8],
+        expected_prefix: &[u8],
+        timeout: Duration,
+    ) -> Result<bool> {
+        self.discard_input()?;
+        let deadline = deadline_after(timeout);
+        self.write_all_deadline(request, deadline)?;
+
+        let mut reply = vec![0u8; expected_prefix.len()];
+        match self.read_exact_deadline(&mut reply, deadline) {
+            Ok(()) => Ok(reply == expected_prefix),
+            Err(BitcoreError::Timeout { .. } | BitcoreError::PartialRead { .. }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// send an AT command and collect its response lines
+    ///
+    /// writes `command` plus CRLF (regardless of `line_ending`), then reads
+    /// lines until a final result code. blank lines and the modem's echo of
+    /// the command are skipped; the information lines in between are
+    /// returned. `ERROR`, `+CME ERROR: n` and the other failure codes are
+    /// `AtCommandFailed`, and no final result within `timeout` is `Timeout`.
+    pub fn at_command(&self, command: &str, timeout: Duration) -> Result<Vec<String>> {
+        self.discard_input()?;
+        let deadline = deadline_after(timeout);
+        self.write_all_deadline(format!("{command}\r\n").as_bytes(), deadline)?;
+
+        let mut lines = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(BitcoreError::timeout(timeout));
+            }
+
+            let line = match self.read_line_with_timeout(remaining) {
+                Ok(line) => line,
+                Err(BitcoreError::Timeout { .. }) => return Err(BitcoreError::timeout(timeout)),
+                Err(e) => return Err(e),
+            };
+            let line = line.trim();
+            if line.is_empty() || line == command.trim() {
+                continue;
+            }
+
+            match at::final_result(line) {
+                Some(FinalResult::Success) => return Ok(lines),
+                Some(FinalResult::Failure(result)) => {
+                    return Err(BitcoreError::AtCommandFailed {
+                        command: command.to_string(),
+                        result,
+                    })
+                }
+                None => lines.push(line.to_string()),
+            }
+        }
+    }
+
+    /// measure the round trip from writing `payload` to receiving `expected`
+    ///
+    /// stale input is discarded first; `expected` may appear anywhere in the
+    /// response. fails with `Timeout` if it has not arrived within `timeout`.
+    pub fn ping(&self, payload: &[u8], expected: &[u8], timeout: Duration) -> Result<Duration> {
+        if expected.is_empty() {
+            return Err(BitcoreError::InvalidParameter {
+                param: "expected".to_string(),
+                reason: "must not be empty".to_string(),
+            });
+        }
+
+        self.discard_input()?;
+
+        let started = Instant::now();
+        let deadline = deadline_after(timeout);
+        self.write_all_deadline(payload, deadline)?;
+
+        let mut received = Vec::new();
+        let mut buffer = [0u8; 64];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(BitcoreError::timeout(timeout));
+            }
+
+            match self.read_for(&mut buffer, remaining) {
+                Ok(bytes_read) => received.extend_from_slice(&buffer[..bytes_read]),
+                Err(BitcoreError::Timeout { .. }) => return Err(BitcoreError::timeout(timeout)),
+                Err(e) => return Err(e),
+            }
+
+            if received.windows(expected.len()).any(|w| w == expected) {
+                let rtt = started.elapsed();
+                debug!("ping round trip {:?}", rtt);
+                return Ok(rtt);
+            }
+        }
+    }
+
+    /// write a known pattern and check that it reads back unchanged
+    ///
+    /// needs a loopback (see `Serial::loopback`) or a peer that echoes
+    pub fn self_test(&self) -> Result<()> {
+        let report = self.integrity_test(PatternKind::Incrementing, 256)?;
+        if !report.is_clean() {
+            return Err(BitcoreError::SelfTestFailed {
+                mismatched: report.mismatched(),
+                length: report.length,
+            });
+        }
+
+        debug!("self-test passed ({} bytes)", report.length);
+        Ok(())
+    }
+
+    /// write `length` bytes of `pattern`, read them back and report corrupted bytes
+    ///
+    /// needs a loopback or a peer that echoes. the read-back is allowed the
+    /// configured timeout plus twice the pattern's transmission time at the
+    /// configured baud rate.
+    pub fn integrity_test(&self, pattern: PatternKind, length: usize) -> Result<IntegrityReport> {
+        let sent = pattern.generate(length);
+        let mut received = vec![0u8; length];
+
+        let transmission = self.settings().wire_time(2.0 * length as f64);
+        let deadline = deadline_after(self.settings().timeout.saturating_add(transmission));
+
+        self.discard_input()?;
+        self.write_all_deadline(&sent, deadline)?;
+        self.read_exact_deadline(&mut received, deadline)?;
+
+        let report = IntegrityReport::compare(pattern, &sent, &received);
+        if !report.is_clean() {
+            warn!(
+                "integrity test: {} of {} bytes corrupted",
+                report.mismatched(),
+                length
+            );
+        }
+        Ok(report)
+    }
+
+    /// baud rates the opened device can be expected to support
+    ///
+    /// serial drivers offer no portable way to enumerate their rates, so this
+    /// currently returns the platform's standard set (see
+    /// `config::standard_baud_rates`). fails with `NotConnected` when closed.
+    pub fn supported_baud_rates(&self) -> Result<Vec<u32>> {
+        self.with_connection(|_| Ok(config::standard_baud_rates().to_vec()))
+    }
+
+    /// receive overruns (bytes the driver dropped) since the port was opened
+    ///
+    /// read from `TIOCGICOUNT` on linux; ports whose driver keeps no counter
+    /// (pseudo terminals, usb adapters without support, mock ports, other
+    /// platforms) fail with an `io::ErrorKind::Unsupported` error. reads also
+    /// log a warning whenever this count grows.
+    pub fn overrun_count(&self) -> Result<u32> {
+        self.with_connection(|conn| Ok(conn.overrun_count()?))
+    }
+
+    /// stop the peer from sending by deasserting RTS
+    ///
+    /// manual receive-side flow control for when processing falls behind. with
+    /// `FlowControl::Hardware` the driver owns RTS and may override this, so use
+    /// it with `FlowControl::None` and a peer that honors CTS.
+    pub fn hold_receive(&self) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.write_request_to_send(false)?))?;
+        debug!("receive held (RTS deasserted)");
+        Ok(())
+    }
+
+    /// let the peer send again by reasserting RTS
+    pub fn resume_receive(&self) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.write_request_to_send(true)?))?;
+        debug!("receive resumed (RTS asserted)");
+        Ok(())
+    }
+
+    /// set the RTS (request to send) output line
+    pub fn set_rts(&self, level: bool) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.write_request_to_send(level)?))
+    }
+
+    /// set the DTR (data terminal ready) output line
+    pub fn set_dtr(&self, level: bool) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.write_data_terminal_ready(level)?))
+    }
+
+    /// read the CTS (clear to send) input line
+    pub fn read_cts(&self) -> Result<bool> {
+        self.with_connection(|conn| Ok(conn.read_clear_to_send()?))
+    }
+
+    /// read the DSR (data set ready) input line
+    pub fn read_dsr(&self) -> Result<bool> {
+        self.with_connection(|conn| Ok(conn.read_data_set_ready()?))
+    }
+
+    /// read the RI (ring indicator) input line
+    pub fn read_ri(&self) -> Result<bool> {
+        self.with_connection(|conn| Ok(conn.read_ring_indicator()?))
+    }
+
+    /// read the DCD (data carrier detect) input line
+    pub fn read_cd(&self) -> Result<bool> {
+        self.with_connection(|conn| Ok(conn.read_carrier_detect()?))
+    }
+
+    /// wait until the modem raises DCD, polling at the poll interval
+    ///
+    /// the usual bring-up before talking to the far end of a dial-up or
+    /// leased line; `Timeout` if no carrier appears within `timeout`
+    pub fn wait_for_carrier(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_line("carrier", timeout, |conn| conn.read_carrier_detect())
+    }
+
+    /// wait until the modem raises RI for an incoming call, polling at the poll interval
+    pub fn wait_for_ring(&self, timeout: Duration) -> Result<()> {
+        self.wait_for_line("ring", timeout, |conn| conn.read_ring_indicator())
+    }
+
+    /// fail with `CarrierLost` unless DCD is up
+    ///
+    /// check between reads to tell a dropped line from a quiet one
+    pub fn ensure_carrier(&self) -> Result<()> {
+        let (carrier, port) = self.with_connection(|conn| {
+            Ok((conn.read_carrier_detect()?, conn.name().unwrap_or_default()))
+        })?;
+        if carrier {
+            return Ok(());
+        }
+        warn!("carrier lost on {}", port);
+        Err(BitcoreError::CarrierLost { port })
+    }
+
+    /// poll a modem status line until it is asserted or `timeout` passes
+    ///
+    /// the connection is locked only for each sample
+    fn wait_for_line(
+        &self,
+        line: &str,
+        timeout: Duration,
+        mut read: impl FnMut(&mut SerialConnection) -> serialport::Result<bool>,
+    ) -> Result<()> {
+        let deadline = deadline_after(timeout);
+        loop {
+            let (asserted, poll_interval) =
+                self.with_connection(|conn| Ok((read(conn)?, conn.poll_interval())))?;
+            if asserted {
+                debug!("{} detected", line);
+                return Ok(());
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(BitcoreError::timeout(timeout));
+            }
+            std::thread::sleep(poll_interval.min(remaining));
+        }
+    }
+
+    /// transmit a break condition for `duration`
+    ///
+    /// the connection stays locked throughout, so no data is sent mid-break
+    pub fn send_break(&self, duration: Duration) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.set_break()?;
+            std::thread::sleep(duration);
+            conn.clear_break()?;
+            debug!("sent {:?} break", duration);
+            Ok(())
+        })
+    }
+
+    /// start transmitting a break condition until `clear_break`
+    pub fn set_break(&self) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.set_break()?))
+    }
+
+    /// stop transmitting a break condition
+    pub fn clear_break(&self) -> Result<()> {
+        self.with_connection(|conn| Ok(conn.clear_break()?))
+    }
+
+    /// run `f` against the open connection while holding the lock
+    fn with_connection<T>(&self, f: impl FnOnce(&mut SerialConnection) -> Result<T>) -> Result<T> {
+        let mut conn_lock = self
+            .connection
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+
+        match conn_lock.as_mut() {
+            Some(conn) => f(conn),
+            None => Err(BitcoreError::NotConnected),
+        }
+    }
+
+    /// close the current port and open `new_port` with the same configuration
+    ///
+    /// if `new_port` cannot be opened, the previous port is reopened and the
+    /// open error is returned
+    pub fn switch_port(&self, new_port: &str) -> Result<()> {
+        let mut conn_lock = self
+            .connection
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        let mut path = self
+            .path
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+
+        if let Some(old) = conn_lock.take() {
+            if let Err(e) = old.disconnect() {
+                warn!("error closing previous port: {}", e);
+            }
+        }
+
+        let config = self.settings().clone();
+        match open_connection(new_port, &config) {
+            Ok(connection) => {
+                *conn_lock = Some(connection);
+                *path = Some(new_port.to_string());
+                self.clear_error();
+                info!("switched to serial port: {}", new_port);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("failed to switch to {}: {}", new_port, e);
+                if let Some(old_path) = path.as_deref() {
+                    match open_connection(old_path, &config) {
+                        Ok(connection) => *conn_lock = Some(connection),
+                        Err(reopen) => error!("failed to reopen {}: {}", old_path, reopen),
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// change the baud rate of the open port
+    ///
+    /// also updates the stored config, so clones, reconnects and
+    /// `switch_port` use the new rate.
+    ///
+    /// the rate is read back from the driver afterwards: many only support a
+    /// fixed set and quietly round to the nearest one. if the rate in effect
+    /// is more than 2% off, the previous rate is restored and
+    /// `InvalidParameter` names the rate the driver picked. ports that cannot
+    /// report their rate are not checked.
+    pub fn set_baud_rate(&self, baud_rate: u32) -> Result<()> {
+        if baud_rate == 0 {
+            return Err(BitcoreError::InvalidParameter {
+                param: "baud_rate".to_string(),
+                reason: "must be greater than zero".to_string(),
+            });
+        }
+        let previous = self.baud_rate();
+
+        self.with_connection(|conn| {
+            conn.set_baud_rate(baud_rate)?;
+            if let Ok(actual) = conn.baud_rate() {
+                if !baud_rate_matches(baud_rate, actual) {
+                    if let Err(e) = conn.set_baud_rate(previous) {
+                        warn!("failed to restore baud rate {}: {}", previous, e);
+                    }
+                    return Err(BitcoreError::InvalidParameter {
+                        param: "baud_rate".to_string(),
+                        reason: format!("driver selected {actual} instead of {baud_rate}"),
+                    });
+                }
+                if actual != baud_rate {
+                    debug!("driver selected baud rate {} for {}", actual, baud_rate);
+                }
+            }
+            self.config
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .baud_rate = baud_rate;
+            Ok(())
+        })?;
+        info!("baud rate changed to {}", baud_rate);
+        Ok(())
+    }
+
+    /// change the flow control of the open port
+    ///
+    /// like `set_baud_rate`, the stored config follows, so a reconnect keeps
+    /// the new setting. buffered input is left alone.
+    pub fn set_flow_control(&self, flow_control: FlowControl) -> Result<()> {
+        self.apply_setting(
+            |conn| conn.set_flow_control(flow_control),
+            |config| config.flow_control = flow_control,
+        )?;
+        info!("flow control changed to {:?}", flow_control);
+        Ok(())
+    }
+
+    /// change the parity of the open port, keeping the stored config in step
+    pub fn set_parity(&self, parity: Parity) -> Result<()> {
+        self.apply_setting(
+            |conn| conn.set_parity(parity),
+            |config| config.parity = parity,
+        )?;
+        info!("parity changed to {:?}", parity);
+        Ok(())
+    }
+
+    /// change the stop bits of the open port, keeping the stored config in step
+    pub fn set_stop_bits(&self, stop_bits: StopBits) -> Result<()> {
+        self.apply_setting(
+            |conn| conn.set_stop_bits(stop_bits),
+            |config| config.stop_bits = stop_bits,
+        )?;
+        info!("stop bits changed to {:?}", stop_bits);
+        Ok(())
+    }
+
+    /// change the data bits of the open port, keeping the stored config in step
+    pub fn set_data_bits(&self, data_bits: DataBits) -> Result<()> {
+        self.apply_setting(
+            |conn| conn.set_data_bits(data_bits),
+            |config| config.data_bits = data_bits,
+        )?;
+        info!("data bits changed to {:?}", data_bits);
+        Ok(())
+    }
+
+    /// apply a line setting to the port, then record it in the config
+    ///
+    /// the config is only touched once the port accepted the change, and
+    /// under the connection lock so a concurrent reconnect sees either both
+    /// or neither
+    fn apply_setting(
+        &self,
+        apply: impl FnOnce(&mut SerialConnection) -> serialport::Result<()>,
+        store: impl FnOnce(&mut SerialConfig),
+    ) -> Result<()> {
+        self.with_connection(|conn| {
+            apply(conn)?;
+            store(&mut self.config.write().unwrap_or_else(PoisonError::into_inner));
+            Ok(())
+        })
+    }
+
+    /// forward everything between `a` and `b` until `cancel` is set
+    ///
+    /// a software null-modem: one thread per direction, each holding a port
+    /// only briefly per read. stops early, with the error, if either port fails.
+    pub fn bridge(a: &Serial, b: &Serial, cancel: &AtomicBool) -> Result<BridgeStats> {
+        bridge::run(a, b, cancel, &|_, _| {})
+    }
+
+    /// like `bridge`, but hand every forwarded chunk to `tap` first (sniffing)
+    pub fn bridge_with_tap(
+        a: &Serial,
+        b: &Serial,
+        cancel: &AtomicBool,
+        tap: impl Fn(BridgeDirection, &[u8]) + Sync,
+    ) -> Result<BridgeStats> {
+        bridge::run(a, b, cancel, &tap)
+    }
+
+    /// read on a background thread, calling `on_data` with every chunk
+    ///
+    /// reads go through `read_for`, so filters, stats and `pending_rx`
+    /// apply as usual; other clones can keep writing meanwhile, but their
+    /// reads would race the reader. while disconnected the thread waits for
+    /// `reconnect`. it holds a clone of this `Serial` until the returned
+    /// handle is stopped or dropped, so the port stays open until then.
+    pub fn spawn_reader(&self, on_data: impl Fn(&[u8]) + Send + 'static) -> ReaderHandle {
+        ReaderHandle::spawn(self.clone(), on_data)
+    }
+
+    /// read and write on background threads, through channels
+    ///
+    /// like `spawn_reader`, but received chunks are queued on a channel that
+    /// holds at most `capacity` of them; while it is full nothing more is
+    /// read from the port. data sent through the channel's sender is written
+    /// with `write_all`, and read or write errors arrive on the receiver.
+    /// both threads stop when the returned `SerialChannel` is dropped.
+    pub fn reader_channel(&self, capacity: usize) -> SerialChannel {
+        SerialChannel::spawn(self.clone(), capacity)
+    }
+
+    /// split into a reader and a writer that no longer block each other
+    ///
+    /// the port handle is cloned (`try_clone`), so each half has its own
+    /// handle and lock. the reader keeps the read rate limit and read filter,
+    /// the writer the write rate limit, write filter and heartbeat; the idle
+    /// watchdog is not carried over. other clones of this `Serial` are left
+    /// disconnected.
+    ///
+    /// if the platform or driver cannot clone the handle, the error is
+    /// returned and the connection is left in place: other clones keep
+    /// using it, and without any the port closes along with `self`.
+    pub fn split(self) -> Result<(SerialReader, SerialWriter)> {
+        // clone before taking, so a failed clone does not lose the connection
+        let (connection, writer_connection) = {
+            let mut conn_lock = self
+                .connection
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+            let writer_connection = conn_lock
+                .as_ref()
+                .ok_or(BitcoreError::NotConnected)?
+                .try_clone_connection()?;
+            let connection = conn_lock.take().ok_or(BitcoreError::NotConnected)?;
+            (connection, writer_connection)
+        };
+        let path = self.path.lock().ok().and_then(|path| path.clone());
+
+        let read_config = SerialConfig {
+            heartbeat: None,
+            idle_timeout: None,
+            write_rate_limit: None,
+            ..self.settings().clone()
+        };
+        let write_config = SerialConfig {
+            idle_timeout: None,
+            read_rate_limit: None,
+            ..self.settings().clone()
+        };
+
+        let reader = Self::from_connection(connection, path.clone(), &read_config)?;
+        let writer = Self::from_connection(writer_connection, path, &write_config)?;
+        // input already taken from the port belongs to the reader
+        std::mem::swap(
+            &mut *self
+                .pending_rx
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?,
+            &mut *reader
+                .pending_rx
+                .lock()
+                .map_err(|e| BitcoreError::LockFailed(e.to_string()))?,
+        );
+        set_filter(&reader.read_filter, current_filter(&self.read_filter)?);
+        set_filter(&writer.write_filter, current_filter(&self.write_filter)?);
+
+        debug!("split serial connection into reader and writer");
+        Ok((SerialReader::new(reader), SerialWriter::new(writer)))
+    }
+
+    /// current settings
+    ///
+    /// never hold the guard while taking the connection lock: `set_baud_rate`
+    /// takes the connection lock first and then this one for writing
+    pub(crate) fn settings(&self) -> RwLockReadGuard<'_, SerialConfig> {
+        self.config.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// copy of the current settings, including runtime changes such as
+    /// `set_baud_rate`
+    pub fn config(&self) -> SerialConfig {
+        self.settings().clone()
+    }
+
+    /// current baud rate
+    pub fn baud_rate(&self) -> u32 {
+        self.settings().baud_rate
+    }
+
+    /// configured timeout for reads and writes
+    pub fn timeout(&self) -> Duration {
+        self.settings().timeout
+    }
+
+    /// get port name
+    pub fn port_name(&self) -> Option<String> {
+        let conn_lock = self.connection.lock().ok()?;
+        conn_lock.as_ref()?.name()
+    }
+
+    /// check if connected
+    ///
+    /// false when no port is open, and also while `last_error` holds a fatal
+    /// error: the handle is still there but the device is not usable
+    pub fn is_connected(&self) -> bool {
+        let open = self
+            .connection
+            .lock()
+            .map(|conn| conn.is_some())
+            .unwrap_or(false);
+        open && self.last_error().is_none()
+    }
+
+    /// the last connection-fatal error (such as `Disconnected`), if the port
+    /// has not recovered since
+    ///
+    /// cleared by the next successful read or write, by `switch_port`, or by
+    /// `clear_error`
+    pub fn last_error(&self) -> Option<BitcoreError> {
+        self.last_error.lock().ok()?.clone()
+    }
+
+    /// forget the recorded fatal error
+    pub fn clear_error(&self) {
+        if let Ok(mut last_error) = self.last_error.lock() {
+            *last_error = None;
+        }
+    }
+
+    /// close the port but keep this handle (and every clone) usable
+    ///
+    /// the port is free for other processes until `reconnect`; reads and
+    /// writes fail with `NotConnected` meanwhile
     pub fn disconnect(&self) -> Result<()> {
         let mut conn_lock = self
             .connection
@@ -287,22 +2878,327 @@ impl Serial {
             None => Err(BitcoreError::NotConnected),
         }
     }
+
+    /// reopen the port after `disconnect`, with the same port name and settings
+    ///
+    /// runtime changes such as `set_baud_rate` are kept. fails with
+    /// `AlreadyConnected` while the port is open, and with `InvalidParameter`
+    /// for connections that were not opened by port name
+    pub fn reconnect(&self) -> Result<()> {
+        let path = self
+            .path
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?
+            .clone()
+            .ok_or_else(|| BitcoreError::InvalidParameter {
+                param: "port".to_string(),
+                reason: "connection was not opened by port name".to_string(),
+            })?;
+        let mut conn_lock = self
+            .connection
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        if conn_lock.is_some() {
+            return Err(BitcoreError::AlreadyConnected);
+        }
+
+        let config = self.settings().clone();
+        *conn_lock = Some(open_connection(&path, &config)?);
+        drop(conn_lock);
+
+        self.mark_activity();
+        info!("reconnected to {}", path);
+        Ok(())
+    }
+}
+
+/// classify a failed read or write on `conn`
+///
+/// a vanished device becomes `Disconnected`, and a timed-out port call a
+/// `Timeout` carrying the port's real timeout
+fn port_error(conn: &SerialConnection, op: &str, err: std::io::Error) -> BitcoreError {
+    if BitcoreError::is_disconnect(&err) {
+        let port = conn.name().unwrap_or_default();
+        warn!("{} failed, {} disconnected: {}", op, port, err);
+        BitcoreError::Disconnected { port }
+    } else if err.kind() == std::io::ErrorKind::TimedOut {
+        BitcoreError::timeout(conn.timeout())
+    } else {
+        err.into()
+    }
+}
+
+/// run `send` with the rs-485 transmitter enabled, if rs-485 mode is on
+///
+/// RTS is released again even when `send` fails, so a failed write never
+/// leaves the transceiver driving the bus
+fn rs485_transmit<T>(
+    conn: &mut SerialConnection,
+    rs485: &Rs485Config,
+    timeout: Duration,
+    send: impl FnOnce(&mut SerialConnection) -> Result<T>,
+) -> Result<T> {
+    if !rs485.enabled {
+        return send(conn);
+    }
+
+    conn.write_request_to_send(rs485.rts_active_high)?;
+    if !rs485.delay_before_send.is_zero() {
+        std::thread::sleep(rs485.delay_before_send);
+    }
+
+    // the post-send delay counts from when the bytes have left the uart,
+    // not from when the os accepted them
+    let result = send(conn).and_then(|sent| drain_output(conn, timeout).map(|()| sent));
+
+    if !rs485.delay_after_send.is_zero() {
+        std::thread::sleep(rs485.delay_after_send);
+    }
+    conn.write_request_to_send(!rs485.rts_active_high)?;
+    result
+}
+
+/// put an rs-485 transceiver into receive mode before the first write
+fn init_rs485(conn: &mut SerialConnection, config: &SerialConfig) -> Result<()> {
+    if config.rs485.enabled {
+        conn.write_request_to_send(!config.rs485.rts_active_high)?;
+    }
+    Ok(())
+}
+
+/// flush `conn` and wait for its output buffer to empty, bounded by `timeout`
+fn drain_output(conn: &mut SerialConnection, timeout: Duration) -> Result<()> {
+    conn.flush().map_err(BitcoreError::Io)?;
+
+    let start_time = Instant::now();
+    loop {
+        let pending = conn.bytes_to_write()?;
+        if pending == 0 {
+            return Ok(());
+        }
+        if start_time.elapsed() >= timeout {
+            warn!("{} bytes still queued after flush timeout", pending);
+            return Err(BitcoreError::timeout(timeout));
+        }
+        std::thread::sleep(conn.poll_interval());
+    }
+}
+
+/// the instant `timeout` from now; `Duration::MAX` and other timeouts too
+/// large for `Instant` mean "never" and land a century or so out
+pub(crate) fn deadline_after(timeout: Duration) -> Instant {
+    let now = Instant::now();
+    now.checked_add(timeout)
+        .unwrap_or_else(|| now + Duration::from_secs(u64::from(u32::MAX)))
+}
+
+/// snapshot of the installed filter, if any
+fn current_filter(filter: &RwLock<Option<ByteFilter>>) -> Result<Option<ByteFilter>> {
+    filter
+        .read()
+        .map(|filter| filter.clone())
+        .map_err(|e| BitcoreError::LockFailed(e.to_string()))
+}
+
+fn set_filter(slot: &RwLock<Option<ByteFilter>>, filter: Option<ByteFilter>) {
+    // a poisoned slot only means a filter panicked; replacing it is still fine
+    *slot.write().unwrap_or_else(|e| e.into_inner()) = filter;
+}
+
+/// wait until `bytes` fit under the rate limit, if there is one
+fn throttle(limiter: Option<&Arc<Mutex<RateLimiter>>>, bytes: usize) -> Result<()> {
+    if let Some(limiter) = limiter {
+        // the limiter stays locked while sleeping so concurrent callers queue up
+        let mut limiter = limiter
+            .lock()
+            .map_err(|e| BitcoreError::LockFailed(e.to_string()))?;
+        let wait = limiter.reserve(bytes);
+        if !wait.is_zero() {
+            trace!("throttling {} bytes for {:?}", bytes, wait);
+            std::thread::sleep(wait);
+        }
+    }
+    Ok(())
+}
+
+/// open `port` with the settings from `config`
+fn open_connection(port: &str, config: &SerialConfig) -> Result<SerialConnection> {
+    let port_builder = serialport::new(port, config.baud_rate)
+        .data_bits(config.data_bits)
+        .parity(config.parity)
+        .stop_bits(config.stop_bits)
+        .flow_control(config.flow_control)
+        .timeout(config.timeout);
+    #[cfg(unix)]
+    let port_builder = port_builder.exclusive(config.exclusive);
+
+    let connection = match config.open_timeout {
+        Some(timeout) => connect_within(port_builder, timeout)?,
+        None => SerialConnection::open(port_builder),
+    };
+    let mut connection = connection
+        .map_err(|e| BitcoreError::open_failed(port, e))?
+        .with_poll_interval(config.clamped_poll_interval())
+        .with_trace_bytes(config.trace_bytes);
+
+    info!("connected to serial port: {}", port);
+    init_rs485(&mut connection, config)?;
+
+    if !config.open_settle_delay.is_zero() {
+        debug!(
+            "waiting {:?} for {} to settle",
+            config.open_settle_delay, port
+        );
+        std::thread::sleep(config.open_settle_delay);
+    }
+
+    attach_capture(connection, config)
+}
+
+/// open on a helper thread, waiting at most `timeout` for it
+///
+/// the outer error is the timeout, the inner result the open itself. a port
+/// that opens too late is dropped, and so closed, by the abandoned thread.
+fn connect_within(
+    port_builder: SerialPortBuilder,
+    timeout: Duration,
+) -> Result<serialport::Result<SerialConnection>> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(SerialConnection::open(port_builder));
+    });
+
+    rx.recv_timeout(timeout).map_err(|_| {
+        warn!("port did not open within {:?}", timeout);
+        BitcoreError::timeout(timeout)
+    })
+}
+
+/// fill `buffer` from `conn` without releasing the lock; returns how many
+/// bytes arrived before `deadline`
+fn read_exact_locked(
+    conn: &mut SerialConnection,
+    buffer: &mut [u8],
+    deadline: Instant,
+) -> Result<usize> {
+    let mut total_read = 0;
+    while total_read < buffer.len() {
+        let result = conn.try_read(&mut buffer[total_read..]);
+        match result.map_err(|e| port_error(conn, "read", e))? {
+            0 => match deadline.checked_duration_since(Instant::now()) {
+                Some(remaining) if !remaining.is_zero() => conn.read_waiter().wait(remaining),
+                _ => break,
+            },
+            bytes_read => total_read += bytes_read,
+        }
+    }
+    Ok(total_read)
+}
+
+/// start capturing traffic if the config asks for it
+fn attach_capture(connection: SerialConnection, config: &SerialConfig) -> Result<SerialConnection> {
+    match &config.capture {
+        Some(path) => {
+            debug!("capturing traffic to {}", path.display());
+            Ok(connection.with_capture(CaptureWriter::open(path)?))
+        }
+        None => Ok(connection),
+    }
+}
+
+/// whether the rate a driver picked is close enough to the requested one
+fn baud_rate_matches(requested: u32, actual: u32) -> bool {
+    u64::from(requested.abs_diff(actual)) * 100
+        <= u64::from(requested) * BAUD_RATE_TOLERANCE_PERCENT
+}
+
+/// send one heartbeat if the link has been quiet for `interval`
+///
+/// returns false once the `Serial` is gone and the heartbeat should stop
+fn heartbeat_tick(
+    connection: &Weak<ConnectionSlot>,
+    last_write: &Weak<Mutex<Option<Instant>>>,
+    payload: &[u8],
+    interval: Duration,
+    started: Instant,
+) -> bool {
+    let (Some(connection), Some(last_write)) = (connection.upgrade(), last_write.upgrade()) else {
+        return false;
+    };
+    let Ok(mut conn_lock) = connection.lock() else {
+        return false;
+    };
+    let Some(conn) = conn_lock.as_mut() else {
+        // disconnected for now; `reconnect` may bring the port back
+        return true;
+    };
+    let Ok(mut last_write) = last_write.lock() else {
+        return false;
+    };
+
+    // the connection lock is held, so this cannot interleave with a user write
+    if last_write.unwrap_or(started).elapsed() >= interval {
+        match conn.write_all(payload) {
+            Ok(()) => {
+                trace!("sent heartbeat ({} bytes)", payload.len());
+                *last_write = Some(Instant::now());
+            }
+            Err(e) => warn!("heartbeat write failed: {}", e),
+        }
+    }
+
+    true
+}
+
+/// close the connection if it has been idle for `idle_timeout`
+///
+/// returns false once the `Serial` is gone and the watchdog should stop
+fn idle_tick(
+    connection: &Weak<ConnectionSlot>,
+    last_activity: &Weak<Mutex<Instant>>,
+    idle_timeout: Duration,
+) -> bool {
+    let (Some(connection), Some(last_activity)) = (connection.upgrade(), last_activity.upgrade())
+    else {
+        return false;
+    };
+    let Ok(mut conn_lock) = connection.lock() else {
+        return false;
+    };
+    if conn_lock.is_none() {
+        return true;
+    }
+    let Ok(last_activity) = last_activity.lock() else {
+        return false;
+    };
+
+    if last_activity.elapsed() < idle_timeout {
+        return true;
+    }
+
+    if let Some(conn) = conn_lock.take() {
+        info!("closing serial port after {:?} of inactivity", idle_timeout);
+        if let Err(e) = conn.disconnect() {
+            warn!("failed to close idle port: {}", e);
+        }
+    }
+    true
 }
 
-impl Drop for Serial {
+impl Drop for ConnectionSlot {
+    // runs exactly once, when the last clone or background thread lets go
     fn drop(&mut self) {
-        if let Ok(mut conn_lock) = self.connection.lock() {
-            if let Some(conn) = conn_lock.take() {
-                let res = conn.disconnect();
-                match res {
-                    Ok(_) => {}
-                    Err(e) => {
-                        let err_msg = format!("Failed to drop the port.{e:?}");
-                        error!("{err_msg}");
-                    }
+        let conn_lock = self.0.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Some(conn) = conn_lock.take() {
+            let res = conn.disconnect();
+            match res {
+                Ok(_) => {}
+                Err(e) => {
+                    let err_msg = format!("Failed to drop the port.{e:?}");
+                    error!("{err_msg}");
                 }
-                debug!("serial connection closed");
             }
+            debug!("serial connection closed");
         }
     }
 }
@@ -0,0 +1,116 @@
+// -- independent reader and writer halves
+//
+// `Serial::split` clones the underlying port handle so reads and writes go
+// through separate handles and separate locks: a reader blocked waiting for
+// data no longer holds up a writer on another thread.
+
+use crate::error::Result;
+use crate::simple::Serial;
+use std::time::{Duration, Instant};
+
+/// receiving half of a split `Serial`
+pub struct SerialReader {
+    inner: Serial,
+}
+
+/// transmitting half of a split `Serial`
+pub struct SerialWriter {
+    inner: Serial,
+}
+
+impl SerialReader {
+    pub(crate) fn new(inner: Serial) -> Self {
+        Self { inner }
+    }
+
+    /// see `Serial::read`
+    pub fn read(&self, buffer: &mut [u8]) -> Result<usize> {
+        self.inner.read(buffer)
+    }
+
+    /// see `Serial::read_with_timeout`
+    pub fn read_with_timeout(&self, buffer: &mut [u8], timeout: Duration) -> Result<usize> {
+        self.inner.read_with_timeout(buffer, timeout)
+    }
+
+    /// see `Serial::read_exact`
+    pub fn read_exact(&self, buffer: &mut [u8]) -> Result<()> {
+        self.inner.read_exact(buffer)
+    }
+
+    /// see `Serial::read_exact_deadline`
+    pub fn read_exact_deadline(&self, buffer: &mut [u8], deadline: Instant) -> Result<()> {
+        self.inner.read_exact_deadline(buffer, deadline)
+    }
+
+    /// see `Serial::read_line`
+    pub fn read_line(&self) -> Result<String> {
+        self.inner.read_line()
+    }
+
+    /// see `Serial::read_line_with_timeout`
+    pub fn read_line_with_timeout(&self, timeout: Duration) -> Result<String> {
+        self.inner.read_line_with_timeout(timeout)
+    }
+
+    /// see `Serial::read_until`
+    pub fn read_until(&self, delim: u8, include_delim: bool) -> Result<Vec<u8>> {
+        self.inner.read_until(delim, include_delim)
+    }
+
+    /// see `Serial::read_until_deadline`
+    pub fn read_until_deadline(&self, delim: u8, deadline: Instant) -> Result<Vec<u8>> {
+        self.inner.read_until_deadline(delim, deadline)
+    }
+
+    /// see `Serial::discard_input`
+    pub fn discard_input(&self) -> Result<()> {
+        self.inner.discard_input()
+    }
+
+    /// check if this half is still open
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+}
+
+impl SerialWriter {
+    pub(crate) fn new(inner: Serial) -> Self {
+        Self { inner }
+    }
+
+    /// see `Serial::write`
+    pub fn write(&self, data: &[u8]) -> Result<usize> {
+        self.inner.write(data)
+    }
+
+    /// see `Serial::write_str`
+    pub fn write_str(&self, data: &str) -> Result<usize> {
+        self.inner.write_str(data)
+    }
+
+    /// see `Serial::write_spaced`
+    pub fn write_spaced(&self, data: &[u8], min_gap_since_last: Duration) -> Result<usize> {
+        self.inner.write_spaced(data, min_gap_since_last)
+    }
+
+    /// see `Serial::write_all_deadline`
+    pub fn write_all_deadline(&self, data: &[u8], deadline: Instant) -> Result<()> {
+        self.inner.write_all_deadline(data, deadline)
+    }
+
+    /// see `Serial::flush`
+    pub fn flush(&self) -> Result<()> {
+        self.inner.flush()
+    }
+
+    /// see `Serial::discard_output`
+    pub fn discard_output(&self) -> Result<()> {
+        self.inner.discard_output()
+    }
+
+    /// check if this half is still open
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+}
@@ -0,0 +1,57 @@
+// -- lifetime traffic counters
+//
+// updated on every read and write with relaxed atomics, so keeping them costs
+// no extra locking on the I/O path.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// traffic totals returned by `Serial::stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerialStats {
+    pub bytes_written: u64,
+    pub bytes_read: u64,
+    /// successful write calls
+    pub writes: u64,
+    /// reads from the port that returned data (bytes handed back by a line
+    /// read and returned later are not counted twice)
+    pub reads: u64,
+    /// reads and writes that ran out of time
+    pub timeouts: u64,
+}
+
+/// shared by every clone of a `Serial`
+#[derive(Default)]
+pub(crate) struct StatsCounters {
+    bytes_written: AtomicU64,
+    bytes_read: AtomicU64,
+    writes: AtomicU64,
+    reads: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+impl StatsCounters {
+    pub(crate) fn record_write(&self, bytes: usize) {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_read(&self, bytes: usize) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_timeout(&self) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> SerialStats {
+        SerialStats {
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            writes: self.writes.load(Ordering::Relaxed),
+            reads: self.reads.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// -- serial over tcp
+//
+// terminal servers (ser2net, Moxa NPort, RFC2217 in raw mode) forward a
+// serial port as a plain byte stream. TcpTransport puts that stream behind
+// the Transport trait so Serial::connect_tcp gets the same read, write and
+// framing code as a local port. the server owns the line settings, so baud
+// rate, parity and the control lines are all `Unsupported` here.
+
+use crate::transport::Transport;
+use serialport::ClearBuffer;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+use tracing::debug;
+
+/// how much one `bytes_to_read` check looks ahead into the socket
+const PEEK_LEN: usize = 4096;
+
+/// scheme accepted (and ignored) in front of `host:port`
+const TCP_SCHEME: &str = "tcp://";
+
+/// tcp socket used as a serial transport
+pub struct TcpTransport {
+    stream: TcpStream,
+    timeout: Duration,
+    /// `tcp://peer`, kept so errors still name the server after it hangs up
+    name: String,
+}
+
+impl TcpTransport {
+    /// connect to `addr`, given as `host:port` or `tcp://host:port`
+    pub fn connect(addr: &str) -> io::Result<Self> {
+        let addr = addr.strip_prefix(TCP_SCHEME).unwrap_or(addr);
+        let stream = TcpStream::connect(addr)?;
+        debug!("connected to {}", addr);
+        Self::from_stream(stream)
+    }
+
+    /// wrap an already connected stream
+    pub fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        // serial traffic is small request/reply exchanges; don't batch it
+        stream.set_nodelay(true)?;
+        let name = format!("{TCP_SCHEME}{}", stream.peer_addr()?);
+        Ok(Self {
+            stream,
+            timeout: Duration::ZERO,
+            name,
+        })
+    }
+
+    /// read (or with `peek`, look at) what has already arrived, without waiting
+    ///
+    /// `WouldBlock` when nothing has. the socket's own blocking mode is
+    /// shared with `try_clone` handles (`split`, bridges), so it is left
+    /// alone: a per-call `MSG_DONTWAIT` keeps a blocking write on the other
+    /// half from seeing `WouldBlock` mid-transfer
+    #[cfg(unix)]
+    fn recv_now(&self, buf: &mut [u8], peek: bool) -> io::Result<usize> {
+        let flags = libc::MSG_DONTWAIT | if peek { libc::MSG_PEEK } else { 0 };
+        // SAFETY: the descriptor belongs to self.stream, which outlives the
+        // call, and buf is valid for buf.len() bytes of writes
+        let received = unsafe {
+            libc::recv(
+                self.stream.as_raw_fd(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+                flags,
+            )
+        };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(received as usize)
+    }
+
+    /// without `MSG_DONTWAIT`, the socket is switched to non-blocking for
+    /// the call, which other handles on it briefly see too
+    #[cfg(not(unix))]
+    fn recv_now(&self, buf: &mut [u8], peek: bool) -> io::Result<usize> {
+        self.stream.set_nonblocking(true)?;
+        let result = if peek {
+            self.stream.peek(buf)
+        } else {
+            (&self.stream).read(buf)
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+}
+
+impl Read for TcpTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for TcpTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        let mut peek = [0u8; PEEK_LEN];
+        match self.recv_now(&mut peek, true) {
+            // a readable socket with nothing in it has been closed by the peer
+            Ok(0) => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "tcp peer closed the connection",
+            )
+            .into()),
+            Ok(count) => Ok(count as u32),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        // sent data cannot be recalled, so only input is discarded
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            let mut scratch = [0u8; PEEK_LEN];
+            loop {
+                match self.recv_now(&mut scratch, false) {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        // a zero socket timeout is rejected; both zero and "forever" mean block
+        let socket_timeout = (!timeout.is_zero() && timeout != Duration::MAX).then_some(timeout);
+        self.stream.set_read_timeout(socket_timeout)?;
+        self.stream.set_write_timeout(socket_timeout)?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn try_clone_transport(&self) -> serialport::Result<Box<dyn Transport>> {
+        Ok(Box::new(Self {
+            stream: self.stream.try_clone()?,
+            timeout: self.timeout,
+            name: self.name.clone(),
+        }))
+    }
+}
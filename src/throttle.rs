@@ -0,0 +1,42 @@
+// -- byte-rate limiting
+
+use std::time::{Duration, Instant};
+
+/// token bucket limiting throughput to a number of bytes per second
+///
+/// the bucket holds 100ms worth of bytes, so short bursts go out immediately
+/// while the long-term rate stays at the limit
+pub(crate) struct RateLimiter {
+    bytes_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(bytes_per_sec: u32) -> Self {
+        let bytes_per_sec = f64::from(bytes_per_sec.max(1));
+        let capacity = (bytes_per_sec / 10.0).max(1.0);
+        Self {
+            bytes_per_sec,
+            capacity,
+            tokens: capacity,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// take `bytes` from the bucket and return how long to wait before using them
+    pub(crate) fn reserve(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last_refill).as_secs_f64() * self.bytes_per_sec;
+        self.tokens = (self.tokens + refill).min(self.capacity);
+        self.last_refill = now;
+
+        self.tokens -= bytes as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.bytes_per_sec)
+        }
+    }
+}
@@ -0,0 +1,226 @@
+// -- byte transport underneath a Serial
+//
+// Serial only needs a byte stream plus a handful of port controls. Transport
+// is that surface: serialport ports get it through the Box<dyn SerialPort>
+// impl below, and anything else (a socket, a pty, a test double) can
+// implement it and be handed to Serial::from_transport.
+//
+// Only reads, writes, the input queue and the timeout are required. Line
+// settings, control lines and break default to `Unsupported` errors, so a
+// stream transport implements just what it has.
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// error returned by transport methods a backend does not implement
+pub fn unsupported(what: &str) -> serialport::Error {
+    serialport::Error::new(
+        serialport::ErrorKind::Io(io::ErrorKind::Unsupported),
+        format!("{what} is not supported by this transport"),
+    )
+}
+
+/// byte stream with the port controls `Serial` uses
+pub trait Transport: Read + Write + Send {
+    /// bytes that can be read right now without blocking
+    fn bytes_to_read(&self) -> serialport::Result<u32>;
+
+    /// discard pending input and/or output
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()>;
+
+    /// how long a blocking read waits for data
+    fn timeout(&self) -> Duration;
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()>;
+
+    /// name shown in logs and errors, such as a device path or peer address
+    fn name(&self) -> Option<String> {
+        None
+    }
+
+    /// bytes written but not yet sent; streams without a queue report 0
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    /// second handle on the same underlying stream
+    fn try_clone_transport(&self) -> serialport::Result<Box<dyn Transport>> {
+        Err(unsupported("cloning"))
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Err(unsupported("baud rate"))
+    }
+
+    fn set_baud_rate(&mut self, _baud_rate: u32) -> serialport::Result<()> {
+        Err(unsupported("baud rate"))
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Err(unsupported("data bits"))
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Err(unsupported("data bits"))
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Err(unsupported("flow control"))
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Err(unsupported("flow control"))
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Err(unsupported("parity"))
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Err(unsupported("parity"))
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Err(unsupported("stop bits"))
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Err(unsupported("stop bits"))
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Err(unsupported("RTS"))
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Err(unsupported("DTR"))
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("CTS"))
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("DSR"))
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("ring indicator"))
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Err(unsupported("carrier detect"))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Err(unsupported("break"))
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Err(unsupported("break"))
+    }
+}
+
+/// every serialport port is a full transport
+impl Transport for Box<dyn SerialPort> {
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        (**self).bytes_to_read()
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        (**self).clear(buffer_to_clear)
+    }
+
+    fn timeout(&self) -> Duration {
+        (**self).timeout()
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        (**self).set_timeout(timeout)
+    }
+
+    fn name(&self) -> Option<String> {
+        (**self).name()
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        (**self).bytes_to_write()
+    }
+
+    fn try_clone_transport(&self) -> serialport::Result<Box<dyn Transport>> {
+        Ok(Box::new((**self).try_clone()?))
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        (**self).baud_rate()
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        (**self).set_baud_rate(baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        (**self).data_bits()
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        (**self).set_data_bits(data_bits)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        (**self).flow_control()
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        (**self).set_flow_control(flow_control)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        (**self).parity()
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        (**self).set_parity(parity)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        (**self).stop_bits()
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        (**self).set_stop_bits(stop_bits)
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        (**self).write_request_to_send(level)
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        (**self).write_data_terminal_ready(level)
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        (**self).read_clear_to_send()
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        (**self).read_data_set_ready()
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        (**self).read_ring_indicator()
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        (**self).read_carrier_detect()
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        (**self).set_break()
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        (**self).clear_break()
+    }
+}
@@ -2,9 +2,14 @@
 // these tests require socat to be installed and available in PATH
 // run with: cargo test --test socat_tests -- --ignored
 
-use bitcore::{Serial, SerialConfig};
+use bitcore::correlated::{decode_frame, encode_frame, CorrelatedSerial};
+use bitcore::mock::MockSerial;
+use bitcore::{
+    BitcoreError, BridgeDirection, DeviceRegistry, DualSerial, PatternKind, Serial, SerialConfig,
+};
 use std::process::{Child, Command, Stdio};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tempfile::TempDir;
@@ -29,7 +34,18 @@ impl SocatManager {
         let port1 = temp_dir.path().join("ttyV0").to_string_lossy().to_string();
         let port2 = temp_dir.path().join("ttyV1").to_string_lossy().to_string();
 
-        // create virtual serial port pair using socat
+        let process = Self::spawn(&port1, &port2)?;
+
+        Ok(Self {
+            process,
+            port1,
+            port2,
+            _temp_dir: temp_dir,
+        })
+    }
+
+    /// create virtual serial port pair using socat
+    fn spawn(port1: &str, port2: &str) -> std::io::Result<Child> {
         let process = Command::new("socat")
             .args([
                 "-d",
@@ -43,13 +59,21 @@ impl SocatManager {
 
         // wait a bit for socat to create the ports
         thread::sleep(Duration::from_millis(100));
+        Ok(process)
+    }
 
-        Ok(Self {
-            process,
-            port1,
-            port2,
-            _temp_dir: temp_dir,
-        })
+    /// kill socat and start a fresh pair on the same paths (simulates an unplug)
+    fn restart(&mut self) -> std::io::Result<()> {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+        self.process = Self::spawn(&self.port1, &self.port2)?;
+        Ok(())
+    }
+
+    /// kill socat without restarting it (simulates an unplug that stays unplugged)
+    fn kill(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
     }
 
     /// get the first port path
@@ -357,4 +381,1200 @@ mod socat_integration_tests {
 
         // automatic cleanup on drop
     }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_write_spaced() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let conn1 = create_test_connection(socat.port1()).expect("failed to connect");
+        let gap = Duration::from_millis(150);
+
+        // first write has no predecessor, so it goes out immediately
+        conn1
+            .write_spaced(b"first", gap)
+            .expect("failed to write first message");
+        let first_done = Instant::now();
+
+        // second write must wait for the remainder of the gap
+        conn1
+            .write_spaced(b"second", gap)
+            .expect("failed to write second message");
+        let elapsed = first_done.elapsed();
+
+        assert!(
+            elapsed >= gap,
+            "spaced writes were only {:?} apart (gap {:?})",
+            elapsed,
+            gap
+        );
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_deadline_shared_budget() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let conn1 = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let conn2 = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        conn1.write(b"first\n").expect("failed to write");
+
+        // one budget shared by both reads
+        let budget = Duration::from_millis(300);
+        let start = Instant::now();
+        let deadline = start + budget;
+
+        let first = conn2
+            .read_until_deadline(b'\n', deadline)
+            .expect("first read should succeed within the budget");
+        assert_eq!(first, b"first\n");
+
+        // nothing else is sent, so the second read uses up what is left and times out
+        let second = conn2.read_until_deadline(b'\n', deadline);
+        let elapsed = start.elapsed();
+        assert!(
+            matches!(second, Err(BitcoreError::Timeout { .. })),
+            "expected timeout, got {:?}",
+            second
+        );
+        assert!(elapsed >= budget);
+        assert!(
+            elapsed < budget + Duration::from_millis(150),
+            "second read did not respect the shared deadline: {:?}",
+            elapsed
+        );
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_heartbeat() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_millis(100))
+            .heartbeat(Some((b"HB".to_vec(), Duration::from_millis(50))));
+        let conn1 = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let conn2 = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        // collect whatever arrives while port1 stays idle
+        let mut received = Vec::new();
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(400) {
+            let mut buffer = [0u8; 64];
+            if let Ok(bytes_read) = conn2.read(&mut buffer) {
+                received.extend_from_slice(&buffer[..bytes_read]);
+            }
+        }
+
+        let heartbeats = received.chunks(2).filter(|chunk| chunk == b"HB").count();
+        println!("received {} heartbeats", heartbeats);
+        assert_eq!(
+            received.len(),
+            heartbeats * 2,
+            "unexpected bytes: {:?}",
+            received
+        );
+        assert!(
+            (4..=9).contains(&heartbeats),
+            "expected roughly one heartbeat per 50ms, got {}",
+            heartbeats
+        );
+
+        // no more heartbeats after disconnect
+        conn1.disconnect().expect("failed to disconnect");
+        thread::sleep(Duration::from_millis(150));
+        let mut buffer = [0u8; 64];
+        let _ = conn2.read(&mut buffer);
+        assert!(conn2.read(&mut buffer).is_err(), "heartbeat kept running");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_capture_replay() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let capture_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let capture_path = capture_dir.path().join("session.cap");
+
+        // record a short request/response exchange
+        {
+            let config = SerialConfig::new(115200)
+                .timeout(Duration::from_millis(200))
+                .capture(Some(capture_path.clone()));
+            let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+            let device = create_test_connection(socat.port2()).expect("failed to connect");
+
+            host.write(b"PING\n").expect("failed to write");
+            assert_eq!(device.read_line().expect("device read failed"), "PING");
+            device.write(b"PONG\n").expect("failed to write");
+            assert_eq!(host.read_line().expect("host read failed"), "PONG");
+        }
+
+        // replay it without socat
+        let mock = MockSerial::from_capture(&capture_path).expect("failed to load capture");
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(200));
+        let replay = Serial::from_port(Box::new(mock.clone()), &config).expect("failed to wrap");
+
+        replay
+            .write(b"PING\n")
+            .expect("replayed write should match");
+        assert_eq!(replay.read_line().expect("replayed read failed"), "PONG");
+        assert!(mock.capture_finished());
+
+        // a write that diverges from the capture is reported
+        let mock = MockSerial::from_capture(&capture_path).expect("failed to load capture");
+        let replay = Serial::from_port(Box::new(mock), &config.retries(0)).expect("failed to wrap");
+        assert!(replay.write(b"NOPE\n").is_err());
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_flush_vs_discard() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let conn1 = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let conn2 = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        // transmit_flush delivers: the peer receives everything written before it
+        conn1.write(b"delivered").expect("failed to write");
+        conn1.transmit_flush().expect("transmit_flush failed");
+        let mut buffer = [0u8; 9];
+        conn2.read_exact(&mut buffer).expect("flushed data missing");
+        assert_eq!(&buffer, b"delivered");
+
+        // discard_input throws away data that already arrived
+        conn1.write(b"stale").expect("failed to write");
+        conn1.flush().expect("flush failed");
+        thread::sleep(Duration::from_millis(50));
+        conn2.discard_input().expect("discard_input failed");
+        let mut buffer = [0u8; 16];
+        assert!(
+            conn2.read(&mut buffer).is_err(),
+            "discarded input was still readable"
+        );
+
+        // discard_output succeeds and leaves the link usable
+        conn1.discard_output().expect("discard_output failed");
+        conn1.write(b"fresh").expect("failed to write");
+        conn1.flush().expect("flush failed");
+        let mut buffer = [0u8; 5];
+        conn2
+            .read_exact(&mut buffer)
+            .expect("data after discard missing");
+        assert_eq!(&buffer, b"fresh");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_switch_port() {
+        init_tracing();
+
+        let (socat_a, socat_b) = match (SocatManager::new(), SocatManager::new()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat_a.port1()).expect("failed to connect");
+        let peer_a = create_test_connection(socat_a.port2()).expect("failed to connect");
+        let peer_b = create_test_connection(socat_b.port2()).expect("failed to connect");
+
+        // move the host to the second pair
+        host.switch_port(socat_b.port1()).expect("failed to switch");
+        assert_eq!(host.port_name().as_deref(), Some(socat_b.port1()));
+        host.write(b"on b").expect("failed to write");
+        let mut buffer = [0u8; 4];
+        peer_b
+            .read_exact(&mut buffer)
+            .expect("data did not arrive on b");
+        assert_eq!(&buffer, b"on b");
+        assert!(peer_a.read(&mut buffer).is_err(), "data leaked to a");
+
+        // a failed switch rolls back to the port in use
+        assert!(host.switch_port("/dev/nonexistent_port_12345").is_err());
+        assert!(host.is_connected());
+        host.write(b"back").expect("failed to write after rollback");
+        peer_b
+            .read_exact(&mut buffer)
+            .expect("rolled-back port unusable");
+        assert_eq!(&buffer, b"back");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_correlated_out_of_order() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+        let correlated = Arc::new(CorrelatedSerial::new(host, Duration::from_secs(2)));
+
+        // device collects both requests, then answers the second one first
+        let device_handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            let mut requests = Vec::new();
+            let start = Instant::now();
+            while requests.len() < 2 && start.elapsed() < Duration::from_secs(2) {
+                let mut buffer = [0u8; 64];
+                if let Ok(bytes_read) = device.read(&mut buffer) {
+                    received.extend_from_slice(&buffer[..bytes_read]);
+                }
+                while let Some((id, payload, consumed)) = decode_frame(&received) {
+                    received.drain(..consumed);
+                    requests.push((id, payload));
+                }
+            }
+            for (id, payload) in requests.iter().rev() {
+                let mut reply = b"re:".to_vec();
+                reply.extend_from_slice(payload);
+                let frame = encode_frame(*id, &reply).unwrap();
+                device.write(&frame).expect("device write failed");
+            }
+        });
+
+        let first = {
+            let correlated = Arc::clone(&correlated);
+            thread::spawn(move || correlated.request(1, b"one"))
+        };
+        thread::sleep(Duration::from_millis(20));
+        let second = {
+            let correlated = Arc::clone(&correlated);
+            thread::spawn(move || correlated.request(2, b"two"))
+        };
+
+        assert_eq!(first.join().unwrap().expect("request 1 failed"), b"re:one");
+        assert_eq!(second.join().unwrap().expect("request 2 failed"), b"re:two");
+        device_handle.join().expect("device thread panicked");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_overrun_count() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let serial = create_test_connection(socat.port1()).expect("failed to connect to port1");
+
+        // the count is hardware dependent and ptys usually keep none; the call
+        // must either succeed or report the lack of support cleanly
+        match serial.overrun_count() {
+            Ok(count) => assert_eq!(count, 0, "fresh port should report no overruns"),
+            Err(BitcoreError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Unsupported),
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_ping() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        // device echoes whatever it receives
+        let device_handle = thread::spawn(move || {
+            let mut buffer = [0u8; 64];
+            let start = Instant::now();
+            let mut echoed = 0;
+            while echoed < 5 && start.elapsed() < Duration::from_secs(2) {
+                if let Ok(bytes_read) = device.read(&mut buffer) {
+                    device.write(&buffer[..bytes_read]).expect("echo failed");
+                    echoed += bytes_read;
+                }
+            }
+        });
+
+        let rtt = host
+            .ping(b"PING\n", b"PING", Duration::from_secs(1))
+            .expect("ping failed");
+        assert!(rtt < Duration::from_secs(1), "implausible rtt {rtt:?}");
+        device_handle.join().expect("device thread panicked");
+
+        // nobody answers now
+        assert!(matches!(
+            host.ping(b"PING\n", b"PING", Duration::from_millis(100)),
+            Err(BitcoreError::Timeout { .. })
+        ));
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_open_settle_delay() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let settle = Duration::from_millis(200);
+        let config = SerialConfig::default().open_settle_delay(settle);
+
+        let start = Instant::now();
+        let serial = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        assert!(start.elapsed() >= settle, "open returned before settling");
+        assert!(serial.is_connected());
+
+        // automatic cleanup on drop
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_json_round_trip() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Telemetry {
+            sensor: String,
+            value: f64,
+            ok: bool,
+        }
+
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        let sent = Telemetry {
+            sensor: "temp".to_string(),
+            value: 21.5,
+            ok: true,
+        };
+        host.write_json(&sent).expect("write_json failed");
+        let received: Telemetry = device.read_json().expect("read_json failed");
+        assert_eq!(received, sent);
+
+        device.write_str("{not json}\n").unwrap();
+        assert!(matches!(
+            host.read_json::<Telemetry>(),
+            Err(BitcoreError::Deserialize { .. })
+        ));
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_sample_bursts() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        let device_handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            device.write(b"first").unwrap();
+            thread::sleep(Duration::from_millis(200));
+            device.write(b"second").unwrap();
+        });
+
+        let chunks = host
+            .sample(Duration::from_millis(500))
+            .expect("sample failed");
+        device_handle.join().expect("device thread panicked");
+
+        assert_eq!(chunks.len(), 2, "expected two bursts, got {chunks:?}");
+        assert_eq!(chunks[0].1, b"first");
+        assert_eq!(chunks[1].1, b"second");
+        let gap = chunks[1].0 - chunks[0].0;
+        assert!(gap >= Duration::from_millis(150), "gap too short: {gap:?}");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_global_default_config() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        Serial::set_global_default_config(
+            SerialConfig::new(57600).timeout(Duration::from_millis(123)),
+        );
+        assert_eq!(Serial::global_default_config().baud_rate, 57600);
+
+        let serial = Serial::new(socat.port1());
+        Serial::clear_global_default_config();
+        let serial = serial.expect("failed to connect");
+
+        // the port really opened at the default's rate
+        assert_eq!(serial.baud_rate(), 57600);
+
+        // the distinctive timeout shows up in the timeout error
+        let mut buffer = [0u8; 1];
+        match serial.read(&mut buffer) {
+            Err(BitcoreError::Timeout { timeout_ms }) => assert_eq!(timeout_ms, 123),
+            other => panic!("expected timeout, got {other:?}"),
+        }
+        assert_eq!(Serial::global_default_config().baud_rate, 9600);
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_integrity_patterns() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        let patterns = [
+            PatternKind::Incrementing,
+            PatternKind::Random(42),
+            PatternKind::Walking1s,
+            PatternKind::AllSame(0xa5),
+        ];
+        let length = 512;
+
+        // device echoes everything back
+        let device_handle = thread::spawn(move || {
+            let mut buffer = [0u8; 256];
+            let mut echoed = 0;
+            let start = Instant::now();
+            while echoed < patterns.len() * length && start.elapsed() < Duration::from_secs(5) {
+                if let Ok(bytes_read) = device.read(&mut buffer) {
+                    device.write(&buffer[..bytes_read]).expect("echo failed");
+                    echoed += bytes_read;
+                }
+            }
+        });
+
+        for pattern in patterns {
+            let report = host
+                .integrity_test(pattern, length)
+                .expect("integrity test failed");
+            assert_eq!(report.length, length);
+            assert!(report.is_clean(), "{pattern:?}: {report:?}");
+        }
+        device_handle.join().expect("device thread panicked");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_dual_serial_lifecycle() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let data_config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let control_config = SerialConfig::new(9600).timeout(Duration::from_millis(100));
+        let dual = DualSerial::open(socat.port1(), &data_config, socat.port2(), &control_config)
+            .expect("failed to open dual port");
+        assert!(dual.is_connected());
+
+        // the two channels still carry data independently
+        dual.control.write(b"AT\r").unwrap();
+        let mut buffer = [0u8; 8];
+        let bytes_read = dual.data.read(&mut buffer).unwrap();
+        assert_eq!(&buffer[..bytes_read], b"AT\r");
+
+        dual.close().expect("close failed");
+        assert!(!dual.data.is_connected());
+        assert!(!dual.control.is_connected());
+        assert!(dual.close().is_ok(), "closing twice is harmless");
+
+        dual.reconnect().expect("reconnect failed");
+        assert!(dual.is_connected());
+
+        // a missing control port leaves nothing open
+        assert!(DualSerial::open(
+            socat.port1(),
+            &data_config,
+            "/dev/nonexistent_port_12345",
+            &control_config
+        )
+        .is_err());
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_device_registry() {
+        init_tracing();
+
+        let (dmm_pair, psu_pair) = match (SocatManager::new(), SocatManager::new()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        // each instrument answers with its own name
+        let spawn_instrument = |port: &str, name: &'static [u8]| {
+            let device = create_test_connection(port).expect("failed to connect instrument");
+            thread::spawn(move || {
+                let line = device
+                    .read_until_deadline(b'\n', Instant::now() + Duration::from_secs(2))
+                    .expect("instrument got no request");
+                let mut reply = name.to_vec();
+                reply.extend_from_slice(b":");
+                reply.extend_from_slice(&line);
+                device.write(&reply).expect("instrument reply failed");
+            })
+        };
+        let dmm = spawn_instrument(dmm_pair.port2(), b"dmm");
+        let psu = spawn_instrument(psu_pair.port2(), b"psu");
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(500));
+        let mut registry = DeviceRegistry::new();
+        registry
+            .register("dmm", dmm_pair.port1(), &config)
+            .expect("register dmm");
+        registry
+            .register(
+                "psu",
+                psu_pair.port1(),
+                &config.clone().timeout(Duration::from_secs(1)),
+            )
+            .expect("register psu");
+        assert!(registry.register("dmm", psu_pair.port1(), &config).is_err());
+
+        assert_eq!(
+            registry.send_recv("psu", b"VOLT 5\n", b'\n').unwrap(),
+            b"psu:VOLT 5\n"
+        );
+        assert_eq!(
+            registry.send_recv("dmm", b"MEAS:VOLT?\n", b'\n').unwrap(),
+            b"dmm:MEAS:VOLT?\n"
+        );
+        assert!(registry.send_recv("scope", b"*IDN?\n", b'\n').is_err());
+
+        dmm.join().expect("dmm thread panicked");
+        psu.join().expect("psu thread panicked");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_bridge() {
+        init_tracing();
+
+        let (left, right) = match (SocatManager::new(), SocatManager::new()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        // device_a <-> [left pair] <-> bridge <-> [right pair] <-> device_b
+        let device_a = create_test_connection(left.port1()).expect("failed to connect device a");
+        let device_b = create_test_connection(right.port2()).expect("failed to connect device b");
+        let bridge_a = create_test_connection(left.port2()).expect("failed to connect bridge a");
+        let bridge_b = create_test_connection(right.port1()).expect("failed to connect bridge b");
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let sniffed = Arc::new(Mutex::new(Vec::new()));
+        let bridge_handle = {
+            let cancel = Arc::clone(&cancel);
+            let sniffed = Arc::clone(&sniffed);
+            thread::spawn(move || {
+                Serial::bridge_with_tap(&bridge_a, &bridge_b, &cancel, |direction, data| {
+                    sniffed.lock().unwrap().push((direction, data.to_vec()));
+                })
+            })
+        };
+
+        let deadline = || Instant::now() + Duration::from_secs(1);
+        device_a.write(b"ping\n").unwrap();
+        assert_eq!(
+            device_b.read_until_deadline(b'\n', deadline()).unwrap(),
+            b"ping\n"
+        );
+        device_b.write(b"pong!\n").unwrap();
+        assert_eq!(
+            device_a.read_until_deadline(b'\n', deadline()).unwrap(),
+            b"pong!\n"
+        );
+
+        cancel.store(true, Ordering::Relaxed);
+        let stats = bridge_handle
+            .join()
+            .expect("bridge thread panicked")
+            .expect("bridge failed");
+        assert_eq!(stats.a_to_b, 5);
+        assert_eq!(stats.b_to_a, 6);
+
+        let sniffed = sniffed.lock().unwrap();
+        let a_to_b: Vec<u8> = sniffed
+            .iter()
+            .filter(|(direction, _)| *direction == BridgeDirection::AToB)
+            .flat_map(|(_, data)| data.clone())
+            .collect();
+        assert_eq!(a_to_b, b"ping\n");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_split_no_contention() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_secs(1));
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+        let (reader, writer) = host.split().expect("split failed");
+
+        // the reader waits for a reply the device only sends after the write
+        let reader_handle = thread::spawn(move || reader.read_line());
+        thread::sleep(Duration::from_millis(50));
+
+        let start = Instant::now();
+        writer.write_str("PING\n").expect("write failed");
+        assert!(
+            start.elapsed() < Duration::from_millis(100),
+            "write blocked behind the pending read"
+        );
+
+        let request = device
+            .read_until_deadline(b'\n', Instant::now() + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(request, b"PING\n");
+        device.write_str("PONG\n").unwrap();
+
+        assert_eq!(reader_handle.join().unwrap().unwrap(), "PONG");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_auto_reconnect() {
+        init_tracing();
+
+        let mut socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_millis(200))
+            .reconnect(true)
+            .max_reconnect_attempts(5);
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        host.write(b"before\n")
+            .expect("write before restart failed");
+
+        socat.restart().expect("failed to restart socat");
+        let device = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        // the old pty is gone; the write reopens the port and goes through
+        host.write(b"after\n").expect("write after restart failed");
+        assert!(host.is_connected());
+        let received = device
+            .read_until_deadline(b'\n', Instant::now() + Duration::from_secs(1))
+            .expect("device got nothing");
+        assert_eq!(received, b"after\n");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_write_all_large() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_secs(5));
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let device = Serial::with_config(socat.port2(), &config).expect("failed to connect");
+
+        // far more than the pty buffers hold, so the writer has to wait for the reader
+        let payload = PatternKind::Random(7).generate(64 * 1024);
+        let expected = payload.clone();
+        let reader = thread::spawn(move || {
+            let mut received = vec![0u8; expected.len()];
+            device
+                .read_exact(&mut received)
+                .map(|()| received == expected)
+        });
+
+        host.write_all(&payload).expect("write_all failed");
+        assert!(
+            reader.join().unwrap().expect("payload did not arrive"),
+            "payload corrupted"
+        );
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_slip_round_trip() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let conn1 = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let conn2 = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        // a leading END, as some stacks send to flush line noise
+        conn1.write(&[0xc0]).expect("failed to write");
+
+        let payloads: [&[u8]; 3] = [
+            &[0xc0, 0xdb, 0x00, 0xc0],
+            &[0xdb, 0xdc, 0xdd, 0xdb],
+            &PatternKind::Random(3).generate(200),
+        ];
+        for payload in payloads {
+            conn1.write_slip(payload).expect("failed to write frame");
+        }
+        for payload in payloads {
+            assert_eq!(conn2.read_slip(256).expect("frame missing"), payload);
+        }
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_set_baud_rate() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let conn1 = create_test_connection(socat.port1()).expect("failed to connect to port1");
+        let conn2 = create_test_connection(socat.port2()).expect("failed to connect to port2");
+
+        assert!(matches!(
+            conn1.set_baud_rate(0),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+
+        // ptys ignore the rate, but the change is accepted and the link keeps working
+        conn1
+            .set_baud_rate(921_600)
+            .expect("failed to set baud rate");
+        conn1.write(b"fast").expect("failed to write");
+        let mut buffer = [0u8; 4];
+        conn2
+            .read_exact(&mut buffer)
+            .expect("data missing after baud change");
+        assert_eq!(&buffer, b"fast");
+
+        // a switch reopens with the new rate
+        conn1.switch_port(socat.port1()).expect("failed to reopen");
+        conn1.write(b"again").expect("failed to write after reopen");
+
+        // automatic cleanup on drop
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_read_reports_disconnect() {
+        init_tracing();
+
+        let mut socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect");
+        let port = host.port_name().expect("no port name");
+        socat.kill();
+
+        let mut buffer = [0u8; 16];
+        match host.read_with_timeout(&mut buffer, Duration::from_secs(1)) {
+            Err(BitcoreError::Disconnected { port: gone }) => assert_eq!(gone, port),
+            other => panic!("expected Disconnected, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_connect_first_available() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let ports = ["/nonexistent/ttyUSB0", socat.port1(), socat.port2()];
+        let serial = Serial::connect_first_available(&ports, &SerialConfig::new(115200))
+            .expect("no port opened");
+        assert_eq!(serial.port_name().as_deref(), Some(socat.port1()));
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_disconnect_reconnect() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let host = create_test_connection(socat.port1()).expect("failed to connect");
+        let device = create_test_connection(socat.port2()).expect("failed to connect");
+
+        host.disconnect().expect("disconnect failed");
+        assert!(!host.is_connected());
+        assert!(matches!(host.write(b"x"), Err(BitcoreError::NotConnected)));
+
+        host.reconnect().expect("reconnect failed");
+        assert!(host.is_connected());
+        assert!(matches!(
+            host.reconnect(),
+            Err(BitcoreError::AlreadyConnected)
+        ));
+        host.write(b"back\n").expect("write after reconnect failed");
+        assert_eq!(device.read_line().expect("device got nothing"), "back");
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_read_until_idle() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_secs(2))
+            .inter_byte_timeout(Some(Duration::from_millis(50)));
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let device = create_test_connection(socat.port2()).expect("failed to connect");
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                device.write_all(b"first burst").unwrap();
+                thread::sleep(Duration::from_millis(300));
+                device.write_all(b"second").unwrap();
+            });
+
+            // each burst comes back on its own, without waiting out the timeout
+            let start = Instant::now();
+            assert_eq!(host.read_until_idle().unwrap(), b"first burst");
+            assert!(start.elapsed() < Duration::from_millis(300));
+            assert_eq!(host.read_until_idle().unwrap(), b"second");
+        });
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_read_exact_wakes_on_data() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        // a poll interval far longer than the gaps between chunks: a reader
+        // that slept per poll would need several intervals for 1000 bytes
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_secs(5))
+            .poll_interval(Duration::from_millis(500));
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let device = create_test_connection(socat.port2()).expect("failed to connect");
+
+        let data: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                for chunk in data.chunks(100) {
+                    thread::sleep(Duration::from_millis(20));
+                    device.write_all(chunk).unwrap();
+                }
+            });
+
+            let start = Instant::now();
+            let mut buffer = vec![0u8; data.len()];
+            host.read_exact(&mut buffer).unwrap();
+            assert_eq!(buffer, data);
+            assert!(
+                start.elapsed() < Duration::from_millis(500),
+                "read took {:?}",
+                start.elapsed()
+            );
+        });
+
+        // and the deadline still holds with nothing arriving
+        let start = Instant::now();
+        let mut buffer = [0u8; 10];
+        assert!(matches!(
+            host.read_exact_deadline(&mut buffer, Instant::now() + Duration::from_millis(200)),
+            Err(BitcoreError::Timeout { .. })
+        ));
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(400));
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_exclusive_open() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        // exclusive is the default: a second opener gets a clean error
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let owner = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        assert!(matches!(
+            Serial::with_config(socat.port1(), &config),
+            Err(BitcoreError::PortBusy { .. })
+        ));
+        drop(owner);
+
+        // shared opens coexist
+        let shared = config.exclusive(false);
+        let first = Serial::with_config(socat.port1(), &shared).expect("failed to connect");
+        let second = Serial::with_config(socat.port1(), &shared).expect("shared open failed");
+        assert!(first.is_connected() && second.is_connected());
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_line_read_throughput() {
+        init_tracing();
+
+        let socat = match SocatManager::new() {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_secs(2));
+        let host = Serial::with_config(socat.port1(), &config).expect("failed to connect");
+        let device = create_test_connection(socat.port2()).expect("failed to connect");
+
+        let line = "x".repeat(199);
+        let payload = format!("{line}\n").repeat(50);
+        device.write_all(payload.as_bytes()).unwrap();
+
+        for _ in 0..50 {
+            assert_eq!(host.read_line().unwrap(), line);
+        }
+
+        // 10000 bytes in chunks, not one port read per byte
+        let stats = host.stats();
+        assert_eq!(stats.bytes_read, payload.len() as u64);
+        assert!(stats.reads <= 100, "{} port reads", stats.reads);
+    }
+
+    #[test]
+    #[ignore] // requires socat
+    fn test_socat_detect_device() {
+        init_tracing();
+
+        let (silent, answering) = match (SocatManager::new(), SocatManager::new()) {
+            (Ok(a), Ok(b)) => (a, b),
+            (Err(e), _) | (_, Err(e)) => {
+                eprintln!("skipping test: {}", e);
+                return;
+            }
+        };
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_secs(2));
+        let device = Serial::with_config(answering.port2(), &config).expect("failed to connect");
+        let responder = thread::spawn(move || {
+            let mut request = [0u8; 3];
+            device.read_exact(&mut request).unwrap();
+            assert_eq!(&request, b"ID?");
+            device.write_all(b"ID:7").unwrap();
+        });
+
+        // the silent port costs the probe timeout, not the config timeout
+        let ports = [silent.port1(), answering.port1()];
+        let start = Instant::now();
+        let found =
+            Serial::detect_device(&ports, b"ID?", b"ID:", &config).expect("device not found");
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(found.timeout(), Duration::from_secs(2));
+        responder.join().unwrap();
+
+        match Serial::detect_device(&[silent.port1()], b"ID?", b"ID:", &config) {
+            Err(BitcoreError::NoPortAvailable { attempts }) => {
+                assert!(matches!(attempts[0].1, BitcoreError::ProbeMismatch { .. }));
+            }
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
 }
@@ -1,7 +1,12 @@
 // -- comprehensive tests for bitcore simplified API
 
-use bitcore::{config::RetryConfig, Serial, SerialConfig};
-use std::time::Duration;
+use bitcore::mock::{MockEvent, MockSerial};
+use bitcore::{
+    config::RetryConfig, BitcoreError, DataBits, FlowControl, LineEnding, Parity, PatternKind,
+    Rs485Config, Serial, SerialConfig, StopBits, TextDecoding,
+};
+use serialport::SerialPort;
+use std::time::{Duration, Instant};
 
 /// initialize tracing for tests
 #[allow(clippy::single_component_path_imports)]
@@ -121,11 +126,13 @@ mod unit_tests {
         init_tracing();
 
         // Test the user's use case: storing config and passing &config to with_config
+        #[allow(dead_code)]
         pub struct UartDriver {
             pub config: SerialConfig,
             pub stats: UartStats,
         }
 
+        #[allow(dead_code)]
         pub struct UartStats {
             pub tx: u32,
             pub rx: u32,
@@ -156,4 +163,2205 @@ mod unit_tests {
         let result = driver.connect("/dev/nonexistent_test_port");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_text_decoding_policies() {
+        init_tracing();
+
+        // "caf" followed by a lone latin-1 e-acute, which is invalid utf-8
+        let input = vec![b'c', b'a', b'f', 0xE9];
+
+        match TextDecoding::Strict.decode(input.clone()) {
+            Err(BitcoreError::InvalidUtf8 { bytes }) => assert_eq!(bytes, input),
+            other => panic!("expected InvalidUtf8, got {:?}", other),
+        }
+
+        let lossy = TextDecoding::Lossy.decode(input.clone()).unwrap();
+        assert_eq!(lossy, "caf\u{FFFD}");
+
+        let latin1 = TextDecoding::Latin1.decode(input).unwrap();
+        assert_eq!(latin1, "caf\u{E9}");
+
+        // valid utf-8 decodes identically under every policy except latin-1
+        let valid = "café".as_bytes().to_vec();
+        assert_eq!(TextDecoding::Strict.decode(valid.clone()).unwrap(), "café");
+        assert_eq!(TextDecoding::Lossy.decode(valid).unwrap(), "café");
+
+        // latin-1 stays the default to match the historical read_line behavior
+        assert_eq!(SerialConfig::default().text_decoding, TextDecoding::Latin1);
+        let config = SerialConfig::new(9600).text_decoding(TextDecoding::Strict);
+        assert_eq!(config.text_decoding, TextDecoding::Strict);
+    }
+
+    #[test]
+    fn test_port_glob_matching() {
+        init_tracing();
+
+        use bitcore::ports::{filter_by_glob, glob_match};
+        use serialport::{SerialPortInfo, SerialPortType};
+
+        assert!(glob_match("/dev/ttyUSB*", "/dev/ttyUSB0"));
+        assert!(glob_match("/dev/ttyUSB*", "/dev/ttyUSB12"));
+        assert!(!glob_match("/dev/ttyUSB*", "/dev/ttyACM0"));
+        assert!(glob_match("/dev/tty???0", "/dev/ttyACM0"));
+        assert!(glob_match("/dev/ttyUSB[0-3]", "/dev/ttyUSB2"));
+        assert!(!glob_match("/dev/ttyUSB[0-3]", "/dev/ttyUSB7"));
+        assert!(glob_match("/dev/ttyUSB[!0]", "/dev/ttyUSB1"));
+        assert!(!glob_match("/dev/ttyUSB[!0]", "/dev/ttyUSB0"));
+        assert!(glob_match("COM*", "COM3"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("/dev/ttyUSB0", "/dev/ttyUSB01"));
+
+        let ports = ["/dev/ttyUSB0", "/dev/ttyUSB1", "/dev/ttyACM0", "/dev/ttyS0"]
+            .iter()
+            .map(|name| SerialPortInfo {
+                port_name: name.to_string(),
+                port_type: SerialPortType::Unknown,
+            })
+            .collect::<Vec<_>>();
+
+        let matched: Vec<String> = filter_by_glob(ports, "/dev/ttyUSB*")
+            .into_iter()
+            .map(|port| port.port_name)
+            .collect();
+        assert_eq!(matched, vec!["/dev/ttyUSB0", "/dev/ttyUSB1"]);
+    }
+
+    #[test]
+    fn test_theoretical_throughput() {
+        init_tracing();
+
+        // 8N1: start + 8 data + 1 stop
+        let config = SerialConfig::new(9600);
+        assert_eq!(config.bits_per_frame(), 10);
+        assert_eq!(config.theoretical_bytes_per_sec(), 960.0);
+
+        // 7E1: start + 7 data + parity + 1 stop
+        let mut config = SerialConfig::new(115200);
+        config.data_bits = serialport::DataBits::Seven;
+        config.parity = serialport::Parity::Even;
+        assert_eq!(config.bits_per_frame(), 10);
+        assert_eq!(config.theoretical_bytes_per_sec(), 11520.0);
+
+        // 8N2: start + 8 data + 2 stop
+        let mut config = SerialConfig::new(19200);
+        config.stop_bits = serialport::StopBits::Two;
+        assert_eq!(config.bits_per_frame(), 11);
+        assert!((config.theoretical_bytes_per_sec() - 19200.0 / 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hold_and_resume_receive() {
+        init_tracing();
+
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default())
+            .expect("failed to wrap mock port");
+
+        serial.resume_receive().unwrap();
+        assert!(mock.rts());
+
+        serial.hold_receive().unwrap();
+        assert!(!mock.rts());
+
+        serial.resume_receive().unwrap();
+        assert!(mock.rts());
+
+        assert_eq!(
+            mock.events(),
+            vec![
+                MockEvent::Rts(true),
+                MockEvent::Rts(false),
+                MockEvent::Rts(true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loopback_self_test() {
+        init_tracing();
+
+        let serial = Serial::loopback();
+        assert!(serial.is_connected());
+        serial
+            .self_test()
+            .expect("self-test should pass on loopback");
+
+        // the written pattern round-trips byte for byte
+        let pattern: Vec<u8> = (0..=255u8).rev().collect();
+        assert_eq!(serial.write(&pattern).unwrap(), pattern.len());
+        let mut echoed = vec![0u8; pattern.len()];
+        serial.read_exact(&mut echoed).unwrap();
+        assert_eq!(echoed, pattern);
+    }
+
+    #[test]
+    fn test_open_retry_policy() {
+        init_tracing();
+
+        let retry = RetryConfig::new(3)
+            .with_delay(Duration::from_millis(10))
+            .with_backoff(1.0);
+
+        // a busy port is retried until the attempts run out
+        let mut attempts = 0;
+        let result: Result<(), _> = retry.retry(BitcoreError::is_transient_open_error, || {
+            attempts += 1;
+            Err(BitcoreError::PortBusy {
+                port: "/dev/ttyUSB0".to_string(),
+            })
+        });
+        assert!(matches!(result, Err(BitcoreError::PortBusy { .. })));
+        assert_eq!(attempts, 4);
+
+        // a busy port that frees up succeeds
+        let mut attempts = 0;
+        let result = retry.retry(BitcoreError::is_transient_open_error, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(BitcoreError::PortBusy {
+                    port: "/dev/ttyUSB0".to_string(),
+                })
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // permission denied fails fast without sleeping
+        let mut attempts = 0;
+        let start = std::time::Instant::now();
+        let result: Result<(), _> = retry.retry(BitcoreError::is_transient_open_error, || {
+            attempts += 1;
+            Err(BitcoreError::PermissionDenied {
+                port: "/dev/ttyUSB0".to_string(),
+            })
+        });
+        assert!(matches!(result, Err(BitcoreError::PermissionDenied { .. })));
+        assert_eq!(attempts, 1);
+        assert!(start.elapsed() < Duration::from_millis(10));
+
+        // a missing port counts as not yet enumerated and is retried
+        let start = std::time::Instant::now();
+        let result = Serial::connect_with_retry(
+            "/dev/nonexistent_port_12345",
+            &SerialConfig::default(),
+            &retry,
+        );
+        assert!(result.is_err());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_write_rate_limit() {
+        init_tracing();
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).write_rate_limit(Some(1000));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // 500 bytes at 1000 B/s, minus the 100-byte burst allowance, takes ~400ms
+        let start = std::time::Instant::now();
+        for chunk in [0x55u8; 500].chunks(50) {
+            serial.write(chunk).unwrap();
+        }
+        let elapsed = start.elapsed();
+
+        assert_eq!(mock.tx().len(), 500);
+        assert!(
+            elapsed >= Duration::from_millis(350) && elapsed < Duration::from_millis(700),
+            "rate-limited writes took {:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn test_read_uninit() {
+        init_tracing();
+
+        use std::mem::MaybeUninit;
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.push_rx(b"hello");
+
+        // sentinel values let us check that nothing past the returned length is touched
+        let mut buffer = [MaybeUninit::new(0xAAu8); 64];
+        let read = serial.read_uninit(&mut buffer).unwrap();
+        assert_eq!(read, 5);
+
+        let bytes: Vec<u8> = buffer
+            .iter()
+            .map(|slot| unsafe { slot.assume_init() })
+            .collect();
+        assert_eq!(&bytes[..5], b"hello");
+        assert!(bytes[5..].iter().all(|&b| b == 0xAA));
+
+        // truly uninitialized buffers work too, and an empty port times out
+        let mut buffer = [MaybeUninit::<u8>::uninit(); 16];
+        assert!(matches!(
+            serial.read_uninit(&mut buffer),
+            Err(BitcoreError::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_supported_baud_rates() {
+        init_tracing();
+
+        let serial = Serial::loopback();
+        let rates = serial.supported_baud_rates().unwrap();
+        assert!(rates.contains(&9600));
+        assert!(rates.contains(&115200));
+        assert!(rates.windows(2).all(|pair| pair[0] < pair[1]));
+
+        serial.disconnect().unwrap();
+        assert!(matches!(
+            serial.supported_baud_rates(),
+            Err(BitcoreError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn test_overrun_count_unsupported_on_mock() {
+        let serial = Serial::loopback();
+        match serial.overrun_count() {
+            Err(BitcoreError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::Unsupported),
+            other => panic!("expected unsupported, got {other:?}"),
+        }
+
+        serial.disconnect().unwrap();
+        assert!(matches!(
+            serial.overrun_count(),
+            Err(BitcoreError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn test_write_disconnect_short_circuits_retries() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().retries(3);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // transient failures are retried
+        mock.fail_next_writes(std::io::ErrorKind::Interrupted, 2);
+        assert_eq!(serial.write(b"ok").unwrap(), 2);
+        assert_eq!(mock.pending_write_failures(), 0);
+
+        // a vanished device is reported after the first attempt
+        mock.fail_next_writes(std::io::ErrorKind::BrokenPipe, 3);
+        assert!(matches!(
+            serial.write(b"gone"),
+            Err(BitcoreError::Disconnected { .. })
+        ));
+        assert_eq!(mock.pending_write_failures(), 2);
+        assert_eq!(mock.tx(), b"ok");
+    }
+
+    #[test]
+    fn test_idle_timeout_closes_port() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().idle_timeout(Some(Duration::from_millis(150)));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // activity keeps the port open past the idle window
+        for _ in 0..4 {
+            std::thread::sleep(Duration::from_millis(60));
+            serial.write(b"x").unwrap();
+        }
+        assert!(serial.is_connected());
+
+        std::thread::sleep(Duration::from_millis(400));
+        assert!(!serial.is_connected());
+        assert!(matches!(
+            serial.write(b"x"),
+            Err(BitcoreError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn test_byte_filters() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        serial.set_write_filter(|data| data.to_ascii_uppercase());
+        assert_eq!(serial.write(b"hello").unwrap(), 5);
+        assert_eq!(mock.take_tx(), b"HELLO");
+
+        // output larger than the caller's buffer carries over to the next read
+        serial.set_read_filter(|data| data.iter().flat_map(|&b| [b, b]).collect());
+        mock.push_rx(b"ab");
+        let mut buffer = [0u8; 2];
+        assert_eq!(serial.read(&mut buffer).unwrap(), 2);
+        assert_eq!(&buffer, b"aa");
+        assert_eq!(serial.read(&mut buffer).unwrap(), 2);
+        assert_eq!(&buffer, b"bb");
+
+        serial.clear_filters();
+        serial.write(b"raw").unwrap();
+        assert_eq!(mock.take_tx(), b"raw");
+    }
+
+    #[test]
+    fn test_last_error_tracks_fatal_failures() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        assert!(serial.last_error().is_none());
+
+        // timeouts are not fatal
+        let mut buffer = [0u8; 1];
+        assert!(serial.read(&mut buffer).is_err());
+        assert!(serial.last_error().is_none());
+
+        mock.fail_next_writes(std::io::ErrorKind::BrokenPipe, 1);
+        assert!(serial.write(b"x").is_err());
+        assert!(matches!(
+            serial.last_error(),
+            Some(BitcoreError::Disconnected { .. })
+        ));
+        assert!(!serial.is_connected());
+
+        // the port came back: a successful write clears the state
+        serial.write(b"x").unwrap();
+        assert!(serial.last_error().is_none());
+        assert!(serial.is_connected());
+    }
+
+    #[test]
+    fn test_integrity_patterns() {
+        assert_eq!(PatternKind::Incrementing.generate(3), [0, 1, 2]);
+        assert_eq!(
+            PatternKind::Walking1s.generate(9),
+            [1, 2, 4, 8, 16, 32, 64, 128, 1]
+        );
+        assert_eq!(PatternKind::AllSame(7).generate(2), [7, 7]);
+        assert_eq!(
+            PatternKind::Random(1).generate(16),
+            PatternKind::Random(1).generate(16)
+        );
+        assert_ne!(
+            PatternKind::Random(1).generate(16),
+            PatternKind::Random(2).generate(16)
+        );
+
+        let report = Serial::loopback()
+            .integrity_test(PatternKind::Random(7), 100)
+            .unwrap();
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn test_auto_flush() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        serial.write(b"a").unwrap();
+        assert_eq!(mock.events(), [MockEvent::Write(b"a".to_vec())]);
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().auto_flush(true);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        serial.write_str("ab").unwrap();
+        serial.write(b"c").unwrap();
+        assert_eq!(
+            mock.events(),
+            [
+                MockEvent::Write(b"ab".to_vec()),
+                MockEvent::Flush,
+                MockEvent::Write(b"c".to_vec()),
+                MockEvent::Flush,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_adaptive_timeout() {
+        let base = Duration::from_millis(100);
+
+        // flat timeout unless enabled
+        let config = SerialConfig::new(9600).timeout(base);
+        assert_eq!(config.effective_timeout(4096), base);
+
+        // 8N1 is 10 bits per byte: 960 bytes/s at 9600 baud, 11520 at 115200
+        let slow = SerialConfig::new(9600).timeout(base).adaptive_timeout(true);
+        let fast = SerialConfig::new(115200)
+            .timeout(base)
+            .adaptive_timeout(true);
+        assert_eq!(slow.effective_timeout(960), base + Duration::from_secs(1));
+        assert_eq!(
+            fast.effective_timeout(1152),
+            base + Duration::from_millis(100)
+        );
+        assert_eq!(fast.effective_timeout(0), base);
+    }
+
+    #[test]
+    fn test_write_retry_backoff() {
+        let retry = RetryConfig::new(3)
+            .with_delay(Duration::from_millis(20))
+            .with_backoff(2.0);
+        let expected: Duration = (0..3).map(|attempt| retry.delay_for_attempt(attempt)).sum();
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().retry_config(retry);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.fail_next_writes(std::io::ErrorKind::Other, 10);
+        let start = std::time::Instant::now();
+        assert!(matches!(serial.write(b"x"), Err(BitcoreError::Io(_))));
+        let elapsed = start.elapsed();
+
+        // one initial attempt plus three retries
+        assert_eq!(mock.pending_write_failures(), 6);
+        assert!(elapsed >= expected, "{elapsed:?} < {expected:?}");
+        assert!(
+            elapsed < expected + Duration::from_millis(100),
+            "{elapsed:?}"
+        );
+
+        // the plain retries builder keeps the embedded policy in sync
+        let config = SerialConfig::default().retry_config(retry).retries(5);
+        assert_eq!(config.retry_policy().max_attempts, 5);
+        assert_eq!(config.retry_policy().retry_delay, Duration::from_millis(20));
+        assert_eq!(
+            SerialConfig::default()
+                .retries(2)
+                .retry_policy()
+                .max_attempts,
+            2
+        );
+    }
+
+    #[test]
+    fn test_read_until() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.push_rx(b"OK\x03>AT");
+        assert_eq!(serial.read_until(0x03, true).unwrap(), b"OK\x03");
+        assert_eq!(serial.read_until(b'>', false).unwrap(), b"");
+
+        // no delimiter: the partial data comes back in the error
+        match serial.read_until(b'>', true) {
+            Err(BitcoreError::DelimiterNotFound { partial, .. }) => assert_eq!(partial, b"AT"),
+            other => panic!("expected partial data, got {other:?}"),
+        }
+        assert!(matches!(
+            serial.read_until(b'>', true),
+            Err(BitcoreError::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_framing_builders() {
+        use bitcore::{DataBits, FlowControl, Parity, StopBits};
+
+        let config = SerialConfig::new(1200)
+            .data_bits(DataBits::Seven)
+            .parity(Parity::Even)
+            .stop_bits(StopBits::One)
+            .flow_control(FlowControl::Software);
+        assert_eq!(config.data_bits, DataBits::Seven);
+        assert_eq!(config.parity, Parity::Even);
+        assert_eq!(config.stop_bits, StopBits::One);
+        assert_eq!(config.flow_control, FlowControl::Software);
+        assert_eq!(config.bits_per_frame(), 10);
+    }
+
+    #[test]
+    fn test_split_halves() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        let handle = serial.clone();
+
+        let (reader, writer) = serial.split().unwrap();
+        assert!(!handle.is_connected());
+
+        writer.write_str("AT\r\n").unwrap();
+        assert_eq!(mock.take_tx(), b"AT\r\n");
+
+        mock.push_rx(b"OK\r\n");
+        assert_eq!(reader.read_line().unwrap(), "OK");
+        assert!(reader.is_connected() && writer.is_connected());
+
+        assert!(matches!(handle.split(), Err(BitcoreError::NotConnected)));
+    }
+
+    #[test]
+    fn test_pending_read_does_not_block_write() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().timeout(Duration::from_millis(500));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        let reader = serial.clone();
+        let pending = std::thread::spawn(move || {
+            let mut buffer = [0u8; 8];
+            reader.read(&mut buffer).map(|n| buffer[..n].to_vec())
+        });
+        std::thread::sleep(Duration::from_millis(50));
+
+        // default poll interval is 10ms; allow generous scheduling slack
+        let start = std::time::Instant::now();
+        serial.write(b"cmd").unwrap();
+        assert!(
+            start.elapsed() < Duration::from_millis(40),
+            "write waited {:?} behind a pending read",
+            start.elapsed()
+        );
+
+        mock.push_rx(b"reply");
+        assert_eq!(pending.join().unwrap().unwrap(), b"reply");
+    }
+
+    #[test]
+    fn test_bytes_available() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        assert_eq!(serial.bytes_available().unwrap(), 0);
+
+        mock.push_rx(b"hello");
+        assert_eq!(serial.bytes_available().unwrap(), 5);
+        let mut buffer = [0u8; 2];
+        serial.read(&mut buffer).unwrap();
+        assert_eq!(serial.bytes_available().unwrap(), 3);
+
+        serial.write(b"out").unwrap();
+        assert_eq!(serial.bytes_pending_write().unwrap(), 0);
+
+        serial.disconnect().unwrap();
+        assert!(matches!(
+            serial.bytes_available(),
+            Err(BitcoreError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn test_control_lines() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        // esp32-style reset into bootloader: EN low via RTS, IO0 low via DTR
+        serial.set_dtr(false).unwrap();
+        serial.set_rts(true).unwrap();
+        serial.set_dtr(true).unwrap();
+        serial.set_rts(false).unwrap();
+        assert_eq!(
+            mock.events(),
+            [
+                MockEvent::Dtr(false),
+                MockEvent::Rts(true),
+                MockEvent::Dtr(true),
+                MockEvent::Rts(false),
+            ]
+        );
+        assert!(mock.dtr() && !mock.rts());
+
+        assert!(!serial.read_cts().unwrap());
+        mock.set_cts(true);
+        mock.set_dsr(true);
+        assert!(serial.read_cts().unwrap());
+        assert!(serial.read_dsr().unwrap());
+    }
+
+    #[test]
+    fn test_send_break() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        let start = std::time::Instant::now();
+        serial.send_break(Duration::from_millis(30)).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(30));
+
+        serial.set_break().unwrap();
+        serial.clear_break().unwrap();
+        assert_eq!(
+            mock.events(),
+            [
+                MockEvent::Break(true),
+                MockEvent::Break(false),
+                MockEvent::Break(true),
+                MockEvent::Break(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clear_buffers() {
+        use serialport::ClearBuffer;
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // a stale partial frame does not leak into the next line
+        mock.push_rx(b"TIMED OU");
+        serial.clear_input().unwrap();
+        mock.push_rx(b"OK\n");
+        assert_eq!(serial.read_line().unwrap(), "OK");
+
+        serial.clear_output().unwrap();
+        serial.clear_all().unwrap();
+        assert_eq!(
+            mock.events(),
+            [
+                MockEvent::Clear(ClearBuffer::Input),
+                MockEvent::Clear(ClearBuffer::Output),
+                MockEvent::Clear(ClearBuffer::All),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transaction_is_atomic() {
+        let serial = Serial::loopback();
+
+        // leftovers are cleared before the request goes out
+        serial.write(b"stale").unwrap();
+        assert_eq!(serial.transaction(b"PING\n", b'\n').unwrap(), b"PING\n");
+
+        // concurrent exchanges never see each other's traffic
+        std::thread::scope(|scope| {
+            for id in 0..4 {
+                let serial = &serial;
+                scope.spawn(move || {
+                    for n in 0..20 {
+                        let request = format!("{id}:{n}\n");
+                        let reply = serial.transaction(request.as_bytes(), b'\n').unwrap();
+                        assert_eq!(reply, request.as_bytes());
+                    }
+                });
+            }
+        });
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_serial_round_trip() {
+        let serial = bitcore::AsyncSerial::from_serial(Serial::loopback());
+
+        assert_eq!(serial.write(b"hello\n").await.unwrap(), 6);
+        assert_eq!(serial.read_line().await.unwrap(), "hello");
+
+        serial.write(b"abc").await.unwrap();
+        let mut buffer = [0u8; 8];
+        let bytes_read = serial.read(&mut buffer).await.unwrap();
+        assert_eq!(&buffer[..bytes_read], b"abc");
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_serial_config_serde() {
+        let config: SerialConfig = serde_json::from_str(
+            r#"{"baud_rate": 115200, "timeout_ms": 250, "data_bits": 7,
+                "parity": "even", "stop_bits": 2, "flow_control": "hardware",
+                "text_decoding": "strict"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.baud_rate, 115200);
+        assert_eq!(config.timeout, Duration::from_millis(250));
+        assert_eq!(config.data_bits, bitcore::DataBits::Seven);
+        assert_eq!(config.parity, bitcore::Parity::Even);
+        assert_eq!(config.stop_bits, bitcore::StopBits::Two);
+        assert_eq!(config.flow_control, bitcore::FlowControl::Hardware);
+        assert_eq!(config.text_decoding, TextDecoding::Strict);
+        // unspecified keys keep their defaults
+        assert_eq!(config.retries, SerialConfig::default().retries);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["timeout_ms"], 250);
+        assert_eq!(json["parity"], "even");
+        let round_trip: SerialConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(round_trip.stop_bits, bitcore::StopBits::Two);
+
+        assert!(serde_json::from_str::<SerialConfig>(r#"{"parity": "mark"}"#).is_err());
+    }
+
+    #[test]
+    fn test_config_framing() {
+        let config = SerialConfig::framing(115200, "7e2").unwrap();
+        assert_eq!(config.baud_rate, 115200);
+        assert_eq!(config.data_bits, bitcore::DataBits::Seven);
+        assert_eq!(config.parity, bitcore::Parity::Even);
+        assert_eq!(config.stop_bits, bitcore::StopBits::Two);
+
+        let config = SerialConfig::framing(9600, "8N1").unwrap();
+        assert_eq!(config.bits_per_frame(), 10);
+
+        for spec in ["9Z3", "8N", "8N1 ", "8X1", "4N1", ""] {
+            assert!(
+                matches!(
+                    SerialConfig::framing(9600, spec),
+                    Err(BitcoreError::InvalidParameter { .. })
+                ),
+                "{spec:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_port_usb_filter() {
+        use bitcore::ports::filter_by_usb;
+        use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+
+        let usb = |name: &str, vid, pid| SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid,
+                pid,
+                serial_number: None,
+                manufacturer: None,
+                product: None,
+            }),
+        };
+        let ports = vec![
+            usb("/dev/ttyUSB0", 0x10c4, 0xea60),
+            usb("/dev/ttyUSB1", 0x0403, 0x6001),
+            usb("/dev/ttyUSB2", 0x10c4, 0xea70),
+            SerialPortInfo {
+                port_name: "/dev/ttyS0".to_string(),
+                port_type: SerialPortType::Unknown,
+            },
+        ];
+
+        let names = |ports: Vec<SerialPortInfo>| -> Vec<String> {
+            ports.into_iter().map(|p| p.port_name).collect()
+        };
+        assert_eq!(
+            names(filter_by_usb(ports.clone(), 0x10c4, None)),
+            ["/dev/ttyUSB0", "/dev/ttyUSB2"]
+        );
+        assert_eq!(
+            names(filter_by_usb(ports.clone(), 0x10c4, Some(0xea70))),
+            ["/dev/ttyUSB2"]
+        );
+        assert!(filter_by_usb(ports, 0x1234, None).is_empty());
+    }
+
+    #[test]
+    fn test_read_exact_partial() {
+        let serial = Serial::loopback();
+        let mut buffer = [0u8; 8];
+
+        assert!(matches!(
+            serial.read_exact_deadline(&mut buffer, Instant::now() + Duration::from_millis(20)),
+            Err(BitcoreError::Timeout { .. })
+        ));
+
+        serial.write(b"abc").unwrap();
+        match serial.read_exact_deadline(&mut buffer, Instant::now() + Duration::from_millis(20)) {
+            Err(BitcoreError::PartialRead { got, expected }) => {
+                assert_eq!((got, expected), (3, 8));
+                assert_eq!(&buffer[..got], b"abc");
+            }
+            other => panic!("expected PartialRead, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_serial_stats() {
+        let serial = Serial::loopback();
+        assert_eq!(serial.stats(), bitcore::SerialStats::default());
+
+        serial.write(b"hello").unwrap();
+        serial.write(b"!").unwrap();
+        let mut buffer = [0u8; 16];
+        let bytes_read = serial.read(&mut buffer).unwrap();
+        assert!(serial.read(&mut buffer).is_err());
+
+        let stats = serial.stats();
+        assert_eq!(stats.bytes_written, 6);
+        assert_eq!(stats.writes, 2);
+        assert_eq!(stats.bytes_read, bytes_read as u64);
+        assert_eq!(stats.reads, 1);
+        assert_eq!(stats.timeouts, 1);
+    }
+
+    #[test]
+    fn test_config_poll_interval() {
+        use bitcore::mock::MockSerial;
+
+        assert_eq!(
+            SerialConfig::default().poll_interval,
+            Duration::from_millis(10)
+        );
+
+        // an interval longer than the timeout must not stretch the read
+        let config = SerialConfig::new(9600)
+            .timeout(Duration::from_millis(30))
+            .poll_interval(Duration::from_secs(5));
+        let serial = Serial::from_port(Box::new(MockSerial::new()), &config).unwrap();
+        let start = Instant::now();
+        let mut buffer = [0u8; 4];
+        assert!(serial.read_exact(&mut buffer).is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_cobs_framing() {
+        use bitcore::framing::{cobs_decode, cobs_encode};
+
+        // reference vectors from the COBS paper / wikipedia
+        let vectors: [(&[u8], &[u8]); 5] = [
+            (&[], &[0x01]),
+            (&[0x00], &[0x01, 0x01]),
+            (&[0x00, 0x00], &[0x01, 0x01, 0x01]),
+            (&[0x11, 0x22, 0x00, 0x33], &[0x03, 0x11, 0x22, 0x02, 0x33]),
+            (&[0x11, 0x00, 0x00, 0x00], &[0x02, 0x11, 0x01, 0x01, 0x01]),
+        ];
+        for (raw, encoded) in vectors {
+            assert_eq!(cobs_encode(raw), encoded);
+            assert_eq!(cobs_decode(encoded).unwrap(), raw);
+        }
+
+        // 254 non-zero bytes fill exactly one block
+        let long: Vec<u8> = (1..=254).collect();
+        let encoded = cobs_encode(&long);
+        assert_eq!(encoded.len(), 255);
+        assert_eq!(encoded[0], 0xff);
+        let longer: Vec<u8> = (0..600).map(|i| (i % 255 + 1) as u8).collect();
+        assert_eq!(cobs_decode(&cobs_encode(&longer)).unwrap(), longer);
+        assert_eq!(cobs_decode(&encoded).unwrap(), long);
+
+        // overhead byte pointing past the end, embedded zero
+        assert!(cobs_decode(&[0x05, 0x11]).is_err());
+        assert!(cobs_decode(&[0x03, 0x00, 0x11]).is_err());
+
+        let serial = Serial::loopback();
+        serial.write(&[0x00, 0x00]).unwrap(); // resync delimiters are skipped
+        serial.write_frame(b"a\x00b\nc").unwrap();
+        assert_eq!(serial.read_frame(64).unwrap(), b"a\x00b\nc");
+
+        serial.write_frame(&[0xaa; 32]).unwrap();
+        assert!(matches!(
+            serial.read_frame(8),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_slip_codec() {
+        use bitcore::framing::slip::{slip_decode, slip_encode};
+
+        assert_eq!(
+            slip_encode(&[0x01, 0xc0, 0x02, 0xdb]),
+            [0x01, 0xdb, 0xdc, 0x02, 0xdb, 0xdd, 0xc0]
+        );
+        assert_eq!(
+            slip_decode(&[0xc0, 0x01, 0xdb, 0xdc, 0xdb, 0xdd, 0xc0]).unwrap(),
+            [0x01, 0xc0, 0xdb]
+        );
+        assert!(slip_decode(&[]).unwrap().is_empty());
+
+        for bad in [&[0xdb, 0x01][..], &[0x01, 0xdb], &[0x01, 0xc0, 0x02]] {
+            assert!(matches!(
+                slip_decode(bad),
+                Err(BitcoreError::InvalidParameter { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_checksums() {
+        use bitcore::checksum::{crc16_ccitt, crc16_modbus, crc8};
+
+        // catalogue check values over "123456789"
+        assert_eq!(crc16_modbus(b"123456789"), 0x4b37);
+        assert_eq!(crc16_ccitt(b"123456789"), 0x29b1);
+        assert_eq!(crc8(b"123456789"), 0xf4);
+        // modbus spec: read holding register 0 of slave 1 is sent as 01 03 00 00 00 01 84 0a
+        assert_eq!(
+            crc16_modbus(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]).to_le_bytes(),
+            [0x84, 0x0a]
+        );
+
+        let serial = Serial::loopback();
+        serial.write_with_crc16(b"frame").unwrap();
+        assert_eq!(serial.read_exact_with_crc16(5).unwrap(), b"frame");
+
+        serial.write(b"frame\x00\x00").unwrap();
+        assert!(matches!(
+            serial.read_exact_with_crc16(5),
+            Err(BitcoreError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_modbus_rtu() {
+        use bitcore::mock::MockSerial;
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(19200).timeout(Duration::from_millis(200));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // the slave answers after the master has cleared its input and written
+        let reply_later = |reply: Vec<u8>| {
+            let mock = mock.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(30));
+                mock.push_rx(&reply);
+            })
+        };
+
+        // slave 1, two registers from 0x006b
+        let reply = reply_later(vec![0x01, 0x03, 0x04, 0x02, 0x2b, 0x00, 0x64, 0x8a, 0x68]);
+        assert_eq!(
+            serial.modbus_read_holding(1, 0x006b, 2).unwrap(),
+            [0x022b, 0x0064]
+        );
+        reply.join().unwrap();
+        assert_eq!(
+            mock.take_tx(),
+            [0x01, 0x03, 0x00, 0x6b, 0x00, 0x02, 0xb5, 0xd7]
+        );
+
+        let reply = reply_later(vec![0x01, 0x06, 0x00, 0x01, 0x00, 0x03, 0x98, 0x0b]);
+        serial.modbus_write_single(1, 0x0001, 0x0003).unwrap();
+        reply.join().unwrap();
+
+        // illegal data address
+        let reply = reply_later(vec![0x01, 0x83, 0x02, 0xc0, 0xf1]);
+        let err = serial.modbus_read_holding(1, 0xffff, 1).unwrap_err();
+        reply.join().unwrap();
+        assert!(matches!(
+            err,
+            BitcoreError::ModbusException {
+                function: 0x03,
+                code: 0x02
+            }
+        ));
+        assert!(err.to_string().contains("illegal data address"));
+
+        let reply = reply_later(vec![0x01, 0x06, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00]);
+        assert!(matches!(
+            serial.modbus_write_single(1, 0x0001, 0x0003),
+            Err(BitcoreError::ChecksumMismatch { .. })
+        ));
+        reply.join().unwrap();
+    }
+
+    #[test]
+    fn test_lines_iterator() {
+        let config = SerialConfig::new(9600).timeout(Duration::from_millis(20));
+        let serial =
+            Serial::from_port(Box::new(bitcore::mock::MockSerial::loopback()), &config).unwrap();
+
+        serial.write(b"one\r\ntwo\n").unwrap();
+        let mut lines = serial.lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "one");
+        assert_eq!(lines.next().unwrap().unwrap(), "two");
+        assert!(matches!(
+            lines.next(),
+            Some(Err(BitcoreError::Timeout { .. }))
+        ));
+
+        // a closed port ends the iteration, even when skipping timeouts
+        serial.write(b"three\n").unwrap();
+        let mut lines = serial.lines().skip_timeouts();
+        assert_eq!(lines.next().unwrap().unwrap(), "three");
+        serial.disconnect().unwrap();
+        assert!(matches!(
+            lines.next(),
+            Some(Err(BitcoreError::NotConnected))
+        ));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn test_per_call_timeouts() {
+        // the configured timeout is long; each call overrides it
+        let serial = Serial::loopback();
+        let mut buffer = [0u8; 4];
+
+        let start = Instant::now();
+        assert!(matches!(
+            serial.read_with_timeout(&mut buffer, Duration::from_millis(20)),
+            Err(BitcoreError::Timeout { timeout_ms: 20 })
+        ));
+        assert!(matches!(
+            serial.read_line_with_timeout(Duration::from_millis(20)),
+            Err(BitcoreError::Timeout { timeout_ms: 20 })
+        ));
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        serial.write(b"ok\n").unwrap();
+        assert_eq!(
+            serial
+                .read_line_with_timeout(Duration::from_millis(20))
+                .unwrap(),
+            "ok"
+        );
+    }
+
+    #[test]
+    fn test_blocking_read() {
+        use bitcore::mock::MockSerial;
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(9600).blocking();
+        assert_eq!(config.timeout, Duration::MAX);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // an infinite timeout must neither overflow a deadline nor give up early
+        let feeder = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            mock.push_rx(b"late\n");
+        });
+        assert_eq!(serial.read_line().unwrap(), "late");
+        feeder.join().unwrap();
+    }
+
+    #[test]
+    fn test_mock_pair() {
+        let (host_port, device_port) = MockSerial::pair();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let host = Serial::from_port(Box::new(host_port), &config).unwrap();
+        let device = Serial::from_port(Box::new(device_port.clone()), &config).unwrap();
+
+        host.write(b"PING\n").unwrap();
+        assert_eq!(device.read_line().unwrap(), "PING");
+        device.write(b"PONG\n").unwrap();
+        assert_eq!(host.read_line().unwrap(), "PONG");
+
+        // an empty queue times out instead of returning at once
+        let start = Instant::now();
+        let mut buffer = [0u8; 4];
+        assert!(matches!(
+            device.read(&mut buffer),
+            Err(BitcoreError::Timeout { .. })
+        ));
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        device_port.fail_next_reads(std::io::ErrorKind::BrokenPipe, 1);
+        assert!(matches!(
+            device.read(&mut buffer),
+            Err(BitcoreError::Disconnected { port }) if port == "mock"
+        ));
+    }
+
+    /// bare stream transport: no line settings, everything written is echoed
+    struct EchoTransport {
+        queue: std::collections::VecDeque<u8>,
+        timeout: Duration,
+    }
+
+    impl std::io::Read for EchoTransport {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let count = buf.len().min(self.queue.len());
+            for (slot, byte) in buf.iter_mut().zip(self.queue.drain(..count)) {
+                *slot = byte;
+            }
+            Ok(count)
+        }
+    }
+
+    impl std::io::Write for EchoTransport {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.queue.extend(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl bitcore::Transport for EchoTransport {
+        fn bytes_to_read(&self) -> serialport::Result<u32> {
+            Ok(self.queue.len() as u32)
+        }
+
+        fn clear(&self, _buffer_to_clear: serialport::ClearBuffer) -> serialport::Result<()> {
+            Ok(())
+        }
+
+        fn timeout(&self) -> Duration {
+            self.timeout
+        }
+
+        fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+            self.timeout = timeout;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_transport() {
+        let transport = EchoTransport {
+            queue: Default::default(),
+            timeout: Duration::ZERO,
+        };
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_transport(Box::new(transport), &config).unwrap();
+
+        serial.write(b"hello\n").unwrap();
+        assert_eq!(serial.read_line().unwrap(), "hello");
+
+        // control lines the transport does not have are reported, not faked
+        assert!(serial.set_rts(true).is_err());
+    }
+
+    #[test]
+    fn test_connect_tcp_echo() {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // echo one chunk back, then hang up
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 64];
+            let count = stream.read(&mut buffer).unwrap();
+            stream.write_all(&buffer[..count]).unwrap();
+        });
+
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(500));
+        let serial = Serial::connect_tcp(&format!("tcp://{addr}"), &config).unwrap();
+        serial.write(b"AT\r\n").unwrap();
+        assert_eq!(serial.read_line().unwrap(), "AT");
+        assert!(serial.set_dtr(true).is_err());
+
+        server.join().unwrap();
+        let mut buffer = [0u8; 8];
+        assert!(matches!(
+            serial.read(&mut buffer),
+            Err(BitcoreError::Disconnected { port }) if port == format!("tcp://{addr}")
+        ));
+    }
+
+    #[test]
+    fn test_read_to_vec() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(200));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // more than one scratch buffer's worth comes back in one piece
+        let dump: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        mock.push_rx(&dump);
+        assert_eq!(serial.read_to_vec().unwrap(), dump);
+
+        let start = Instant::now();
+        assert!(matches!(
+            serial.read_to_vec(),
+            Err(BitcoreError::Timeout { .. })
+        ));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_drain_waits_for_output() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        serial.write(b"frame").unwrap();
+        serial.drain().unwrap();
+        assert_eq!(mock.events().last(), Some(&MockEvent::Flush));
+
+        // a transmitter that never empties runs into the timeout
+        mock.set_pending_tx(3);
+        assert!(matches!(serial.drain(), Err(BitcoreError::Timeout { .. })));
+    }
+
+    #[test]
+    fn test_rs485_toggles_rts_around_writes() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200)
+            .rs485(Rs485Config::enabled().delay_after_send(Duration::from_millis(5)));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        // the transceiver starts out receiving
+        assert_eq!(mock.events(), vec![MockEvent::Rts(false)]);
+
+        serial.write(b"\x01\x03").unwrap();
+        assert_eq!(
+            mock.events()[1..],
+            [
+                MockEvent::Rts(true),
+                MockEvent::Write(b"\x01\x03".to_vec()),
+                MockEvent::Flush,
+                MockEvent::Rts(false),
+            ]
+        );
+
+        // a failed write still releases the bus
+        mock.fail_next_writes(std::io::ErrorKind::BrokenPipe, 1);
+        assert!(serial.write(b"x").is_err());
+        assert!(!mock.rts());
+    }
+
+    #[test]
+    fn test_config_validate() {
+        assert!(SerialConfig::default().validate().is_ok());
+        // short timeouts only warn
+        assert!(SerialConfig::new(9600)
+            .timeout(Duration::ZERO)
+            .validate()
+            .is_ok());
+
+        // rejected before the (nonexistent) port is even looked at
+        let result = Serial::with_config("/nonexistent/ttyUSB9", &SerialConfig::new(0));
+        assert!(matches!(
+            result,
+            Err(BitcoreError::InvalidParameter { ref param, .. }) if param == "baud_rate"
+        ));
+    }
+
+    #[test]
+    fn test_timeout_error_carries_real_timeout() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_millis(100))
+            .retries(0);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.fail_next_writes(std::io::ErrorKind::TimedOut, 1);
+        let err = serial.write(b"x").unwrap_err();
+        assert!(matches!(err, BitcoreError::Timeout { timeout_ms: 100 }));
+        assert_eq!(err.to_string(), "operation timed out after 100ms");
+    }
+
+    #[test]
+    fn test_error_source_chain() {
+        use std::error::Error;
+
+        let err = BitcoreError::Io(std::io::Error::other("uart on fire"));
+        let source = err.source().expect("io error has a source");
+        assert_eq!(source.to_string(), "uart on fire");
+
+        assert!(BitcoreError::NotConnected.source().is_none());
+    }
+
+    #[test]
+    fn test_connect_first_available_lists_failures() {
+        let ports = ["/nonexistent/ttyUSB0", "/nonexistent/ttyUSB1"];
+        let err = match Serial::connect_first_available(&ports, &SerialConfig::default()) {
+            Err(e) => e,
+            Ok(_) => panic!("nonexistent ports opened"),
+        };
+        let BitcoreError::NoPortAvailable { attempts } = &err else {
+            panic!("expected NoPortAvailable, got {err:?}");
+        };
+        let tried: Vec<&str> = attempts.iter().map(|(port, _)| port.as_str()).collect();
+        assert_eq!(tried, ports);
+        assert!(err.to_string().contains("/nonexistent/ttyUSB1"));
+    }
+
+    #[test]
+    fn test_probe() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // stale bytes from before the probe are discarded
+        mock.push_rx(b"garbage");
+        assert!(!serial.probe(b"ID?\n", b"ARDUINO").unwrap());
+        assert_eq!(mock.take_tx(), b"ID?\n");
+
+        // a device answering with the expected prefix matches
+        let (host, device) = MockSerial::pair();
+        let serial = Serial::from_port(Box::new(host), &config).unwrap();
+        let responder = std::thread::spawn(move || {
+            use serialport::SerialPort;
+            use std::io::Write;
+
+            let mut device = device;
+            let start = Instant::now();
+            while device.bytes_to_read().unwrap() == 0 && start.elapsed() < Duration::from_secs(1) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            device.write_all(b"ARDUINO UNO\n").unwrap();
+        });
+        assert!(serial.probe(b"ID?\n", b"ARDUINO").unwrap());
+        responder.join().unwrap();
+    }
+
+    #[test]
+    fn test_settings_getters() {
+        let config = SerialConfig::new(57600).timeout(Duration::from_millis(250));
+        let serial = Serial::from_port(Box::new(MockSerial::new()), &config).unwrap();
+        assert_eq!(serial.baud_rate(), 57600);
+        assert_eq!(serial.timeout(), Duration::from_millis(250));
+
+        serial.set_baud_rate(115200).unwrap();
+        assert_eq!(serial.baud_rate(), 115200);
+        assert_eq!(serial.config().baud_rate, 115200);
+    }
+
+    #[test]
+    fn test_reconnect_needs_port_name() {
+        let serial =
+            Serial::from_port(Box::new(MockSerial::new()), &SerialConfig::default()).unwrap();
+        serial.disconnect().unwrap();
+        assert!(!serial.is_connected());
+        assert!(matches!(
+            serial.reconnect(),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+    }
+
+    #[test]
+    fn test_clones_share_port_until_last_drop() {
+        let serial = Serial::loopback();
+        let clone = serial.clone();
+
+        clone.write(b"shared\n").unwrap();
+        assert_eq!(serial.read_line().unwrap(), "shared");
+
+        // dropping a clone from another thread leaves the port open
+        std::thread::spawn(move || drop(clone)).join().unwrap();
+        assert!(serial.is_connected());
+        serial.write(b"still open\n").unwrap();
+        assert_eq!(serial.read_line().unwrap(), "still open");
+    }
+
+    #[test]
+    fn test_read_line_limited() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.push_rx(b"short\r\n");
+        assert_eq!(serial.read_line_limited(5).unwrap(), "short");
+
+        mock.push_rx(b"much too long\n");
+        assert!(matches!(
+            serial.read_line_limited(8),
+            Err(BitcoreError::LineTooLong { max_len: 8 })
+        ));
+    }
+
+    #[test]
+    fn test_read_line_bytes_and_utf8() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200)
+            .timeout(Duration::from_millis(100))
+            .text_decoding(TextDecoding::Strict);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.push_rx("température 21°C\r\n".as_bytes());
+        assert_eq!(serial.read_line().unwrap(), "température 21°C");
+
+        mock.push_rx(b"\xff\xfe raw\n");
+        assert_eq!(serial.read_line_bytes().unwrap(), b"\xff\xfe raw");
+    }
+
+    #[test]
+    fn test_hex_dump_format() {
+        use bitcore::hexdump::hex_dump;
+
+        assert_eq!(
+            hex_dump(b"Hello\r\n"),
+            format!("0000  48 65 6c 6c 6f 0d 0a{}  Hello..", " ".repeat(27))
+        );
+
+        let dump = hex_dump(&[0u8; 17]);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("0010  00  "));
+
+        // tracing on must not change what goes over the wire
+        let config = SerialConfig::default().trace_bytes(true);
+        let serial = Serial::from_port(Box::new(MockSerial::loopback()), &config).unwrap();
+        serial.write(b"ping\n").unwrap();
+        assert_eq!(serial.read_line().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_length_prefixed_frames() {
+        use bitcore::framing::LengthPrefix;
+
+        let serial = Serial::loopback();
+        serial
+            .write_length_prefixed(LengthPrefix::U16Be, b"hello")
+            .unwrap();
+        assert_eq!(
+            serial
+                .read_length_prefixed(LengthPrefix::U16Be, 64)
+                .unwrap(),
+            b"hello"
+        );
+
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        serial
+            .write_length_prefixed(LengthPrefix::U32Le, &[0xaa; 3])
+            .unwrap();
+        assert_eq!(mock.take_tx(), [3, 0, 0, 0, 0xaa, 0xaa, 0xaa]);
+
+        // a corrupt header is rejected instead of allocating 4 GiB
+        mock.push_rx(&[0xff, 0xff, 0xff, 0xff]);
+        assert!(matches!(
+            serial.read_length_prefixed(LengthPrefix::U32Be, 1024),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+    }
+
+    /// numbers as decimal text, one per `;`-terminated frame
+    struct NumberCodec;
+
+    impl bitcore::Codec for NumberCodec {
+        type Cmd = u32;
+        type Resp = u32;
+
+        fn encode(&self, cmd: &u32) -> Vec<u8> {
+            format!("{cmd};").into_bytes()
+        }
+
+        fn decode(&self, frame: &[u8]) -> bitcore::Result<u32> {
+            std::str::from_utf8(frame)
+                .ok()
+                .and_then(|text| text.parse().ok())
+                .ok_or_else(|| BitcoreError::Deserialize {
+                    reason: format!("not a number: {frame:?}"),
+                })
+        }
+
+        fn delimiter(&self) -> u8 {
+            b';'
+        }
+    }
+
+    #[test]
+    fn test_query_with_codec() {
+        // the loopback answers every command with itself
+        let serial = Serial::loopback();
+        assert_eq!(serial.query(&NumberCodec, &4711).unwrap(), 4711);
+
+        serial.write(b"junk").unwrap();
+        assert_eq!(serial.query(&NumberCodec, &7).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_clear_before_write() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().clear_before_write(true);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        serial.write_all(b"CMD\n").unwrap();
+        assert_eq!(
+            mock.events(),
+            vec![
+                MockEvent::Clear(serialport::ClearBuffer::Output),
+                MockEvent::Write(b"CMD\n".to_vec()),
+            ]
+        );
+
+        // off by default
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        serial.write(b"CMD\n").unwrap();
+        assert_eq!(mock.events(), vec![MockEvent::Write(b"CMD\n".to_vec())]);
+    }
+
+    #[test]
+    fn test_runtime_line_settings() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        serial.set_flow_control(FlowControl::Software).unwrap();
+        serial.set_parity(Parity::Even).unwrap();
+        serial.set_stop_bits(StopBits::Two).unwrap();
+        serial.set_data_bits(DataBits::Seven).unwrap();
+
+        assert_eq!(mock.flow_control().unwrap(), FlowControl::Software);
+        assert_eq!(mock.parity().unwrap(), Parity::Even);
+        assert_eq!(mock.stop_bits().unwrap(), StopBits::Two);
+        assert_eq!(mock.data_bits().unwrap(), DataBits::Seven);
+
+        let config = serial.config();
+        assert_eq!(config.flow_control, FlowControl::Software);
+        assert_eq!(config.parity, Parity::Even);
+        assert_eq!(config.stop_bits, StopBits::Two);
+        assert_eq!(config.data_bits, DataBits::Seven);
+
+        serial.disconnect().unwrap();
+        assert!(matches!(
+            serial.set_flow_control(FlowControl::None),
+            Err(BitcoreError::NotConnected)
+        ));
+    }
+
+    #[test]
+    fn test_write_line_endings() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        assert_eq!(serial.write_line("AT").unwrap(), 4);
+        assert_eq!(mock.tx(), b"AT\r\n");
+
+        for (ending, expected) in [
+            (LineEnding::Lf, &b"AT\n"[..]),
+            (LineEnding::Cr, b"AT\r"),
+            (LineEnding::None, b"AT"),
+        ] {
+            let mock = MockSerial::new();
+            let config = SerialConfig::default().line_ending(ending);
+            let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+            serial.write_line("AT").unwrap();
+            assert_eq!(mock.tx(), expected);
+        }
+    }
+
+    #[test]
+    fn test_at_command() {
+        use bitcore::at::{final_result, FinalResult};
+
+        assert_eq!(final_result("OK\r"), Some(FinalResult::Success));
+        assert_eq!(final_result("CONNECT 9600"), Some(FinalResult::Success));
+        assert_eq!(
+            final_result("+CME ERROR: 10"),
+            Some(FinalResult::Failure("+CME ERROR: 10".to_string()))
+        );
+        assert_eq!(final_result("+CSQ: 21,0"), None);
+
+        let (host, device) = MockSerial::pair();
+        let serial = Serial::from_port(Box::new(host), &SerialConfig::default()).unwrap();
+        let modem = std::thread::spawn(move || {
+            use serialport::SerialPort;
+            use std::io::{Read, Write};
+
+            let mut device = device;
+            for reply in [
+                &b"AT+CSQ\r\r\n+CSQ: 21,0\r\n\r\nOK\r\n"[..],
+                b"\r\n+CME ERROR: 10\r\n",
+            ] {
+                let mut command = Vec::new();
+                let start = Instant::now();
+                while !command.ends_with(b"\r\n") && start.elapsed() < Duration::from_secs(1) {
+                    let mut buffer = [0u8; 32];
+                    if device.bytes_to_read().unwrap() > 0 {
+                        let n = device.read(&mut buffer).unwrap();
+                        command.extend_from_slice(&buffer[..n]);
+                    } else {
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                }
+                device.write_all(reply).unwrap();
+            }
+        });
+
+        let lines = serial.at_command("AT+CSQ", Duration::from_secs(1)).unwrap();
+        assert_eq!(lines, vec!["+CSQ: 21,0".to_string()]);
+
+        match serial.at_command("AT+CPIN?", Duration::from_secs(1)) {
+            Err(BitcoreError::AtCommandFailed { command, result }) => {
+                assert_eq!(command, "AT+CPIN?");
+                assert_eq!(result, "+CME ERROR: 10");
+            }
+            other => panic!("expected AtCommandFailed, got {other:?}"),
+        }
+        modem.join().unwrap();
+
+        // no final result code in time
+        assert!(matches!(
+            serial.at_command("AT", Duration::from_millis(50)),
+            Err(BitcoreError::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_debug_does_not_block() {
+        use serialport::SerialPort;
+
+        let (host, device) = MockSerial::pair();
+        let config = SerialConfig::new(57600).timeout(Duration::from_millis(300));
+        let serial = Serial::from_port(Box::new(host), &config).unwrap();
+        let debug = format!("{serial:?}");
+        assert!(debug.contains("connected: true"));
+        assert!(debug.contains("baud_rate: 57600"));
+
+        // a transaction waiting for its reply holds the connection lock
+        let holder = serial.clone();
+        let worker = std::thread::spawn(move || holder.transaction(b"PING\n", b'\n'));
+        let start = Instant::now();
+        while device.bytes_to_read().unwrap() == 0 && start.elapsed() < Duration::from_secs(1) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let started = Instant::now();
+        assert!(format!("{serial:?}").contains("connected: <locked>"));
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert!(worker.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_open_timeout_keeps_open_errors() {
+        let config = SerialConfig::default().open_timeout(Duration::from_secs(2));
+        assert_eq!(config.open_timeout, Some(Duration::from_secs(2)));
+        assert_eq!(SerialConfig::default().open_timeout, None);
+
+        // an open that fails in time reports its own error, not a timeout
+        let start = Instant::now();
+        let err = Serial::with_config("/nonexistent/ttyUSB0", &config).unwrap_err();
+        assert!(!matches!(err, BitcoreError::Timeout { .. }), "{err:?}");
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_retry_predicate() {
+        assert!(BitcoreError::Timeout { timeout_ms: 5 }.is_transient());
+        assert!(BitcoreError::Io(std::io::ErrorKind::Interrupted.into()).is_transient());
+        assert!(!BitcoreError::Io(std::io::ErrorKind::InvalidInput.into()).is_transient());
+        assert!(!BitcoreError::NotConnected.is_transient());
+
+        // errors that cannot succeed on a second try fail without the delay
+        let retry = RetryConfig::new(3).with_delay(Duration::from_millis(200));
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().retry_config(retry);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        mock.fail_next_writes(std::io::ErrorKind::InvalidInput, 4);
+        let start = Instant::now();
+        assert!(serial.write(b"x").is_err());
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert_eq!(mock.pending_write_failures(), 3);
+
+        // a custom predicate replaces the default
+        let retry = retry
+            .with_delay(Duration::from_millis(1))
+            .with_should_retry(|_| true);
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().retry_config(retry);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        mock.fail_next_writes(std::io::ErrorKind::InvalidInput, 2);
+        assert_eq!(serial.write(b"x").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_read_retries_transient_errors() {
+        let retry = RetryConfig::new(3).with_delay(Duration::from_millis(1));
+        let config = SerialConfig::default()
+            .timeout(Duration::from_millis(100))
+            .retry_config(retry);
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        mock.push_rx(b"data");
+        mock.fail_next_reads(std::io::ErrorKind::Interrupted, 2);
+        let mut buffer = [0u8; 8];
+        assert_eq!(serial.read(&mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], b"data");
+
+        // more failures than retries, or a permanent error, still fail
+        mock.push_rx(b"data");
+        mock.fail_next_reads(std::io::ErrorKind::Interrupted, 4);
+        assert!(matches!(serial.read(&mut buffer), Err(BitcoreError::Io(_))));
+        mock.fail_next_reads(std::io::ErrorKind::InvalidInput, 1);
+        assert!(matches!(serial.read(&mut buffer), Err(BitcoreError::Io(_))));
+        assert_eq!(serial.read(&mut buffer).unwrap(), 4);
+
+        // running out of time with nothing received is a timeout, not retried
+        let start = Instant::now();
+        assert!(matches!(
+            serial.read(&mut buffer),
+            Err(BitcoreError::Timeout { timeout_ms: 100 })
+        ));
+        assert!(start.elapsed() < Duration::from_millis(200));
+
+        // backoff longer than the read timeout is cut short at the deadline
+        let retry = RetryConfig::new(3).with_delay(Duration::from_secs(1));
+        let config = SerialConfig::default()
+            .timeout(Duration::from_millis(100))
+            .retry_config(retry);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        mock.fail_next_reads(std::io::ErrorKind::Interrupted, 3);
+        let start = Instant::now();
+        assert!(matches!(serial.read(&mut buffer), Err(BitcoreError::Io(_))));
+        assert!(start.elapsed() < Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_write_read_exact() {
+        use serialport::SerialPort;
+        use std::io::{Read, Write};
+
+        let (host, device) = MockSerial::pair();
+        let config = SerialConfig::default().timeout(Duration::from_millis(200));
+        let serial = Serial::from_port(Box::new(host), &config).unwrap();
+
+        let responder = std::thread::spawn(move || {
+            let mut device = device;
+            // stale bytes, dropped by the clear before the request
+            device.write_all(b"junk").unwrap();
+            for reply in [&b"\x01\x02\x03\x04"[..], b"\x05\x06"] {
+                let start = Instant::now();
+                while device.bytes_to_read().unwrap() == 0
+                    && start.elapsed() < Duration::from_secs(1)
+                {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                let mut request = [0u8; 8];
+                let _ = device.read(&mut request).unwrap();
+                device.write_all(reply).unwrap();
+            }
+        });
+
+        std::thread::sleep(Duration::from_millis(20));
+        let mut response = [0u8; 4];
+        serial.write_read_exact(b"GET", &mut response).unwrap();
+        assert_eq!(response, [1, 2, 3, 4]);
+
+        match serial.write_read_exact(b"GET", &mut response) {
+            Err(BitcoreError::PartialRead {
+                got: 2,
+                expected: 4,
+            }) => {
+                assert_eq!(&response[..2], [5, 6]);
+            }
+            other => panic!("expected PartialRead, got {other:?}"),
+        }
+        responder.join().unwrap();
+
+        assert!(matches!(
+            serial.write_read_exact(b"GET", &mut response),
+            Err(BitcoreError::Timeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_carrier_and_ring() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().poll_interval(Duration::from_millis(5));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        assert!(matches!(
+            serial.wait_for_carrier(Duration::from_millis(30)),
+            Err(BitcoreError::Timeout { timeout_ms: 30 })
+        ));
+        assert!(matches!(
+            serial.ensure_carrier(),
+            Err(BitcoreError::CarrierLost { .. })
+        ));
+
+        let modem = mock.clone();
+        let raise = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(30));
+            modem.set_carrier_detect(true);
+            modem.set_ring_indicator(true);
+        });
+        serial.wait_for_carrier(Duration::from_secs(1)).unwrap();
+        serial.wait_for_ring(Duration::from_secs(1)).unwrap();
+        serial.ensure_carrier().unwrap();
+        assert!(serial.read_cd().unwrap() && serial.read_ri().unwrap());
+        raise.join().unwrap();
+    }
+
+    #[test]
+    fn test_short_writes() {
+        let mock = MockSerial::new();
+        mock.set_max_write_len(Some(3));
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        // a plain write reports what the port took
+        assert_eq!(serial.write(b"ABCDEFG").unwrap(), 3);
+        assert_eq!(mock.take_tx(), b"ABC");
+
+        // strict mode keeps writing until everything is out
+        let mock = MockSerial::new();
+        mock.set_max_write_len(Some(3));
+        let config = SerialConfig::default()
+            .strict_writes(true)
+            .retry_config(RetryConfig::new(1).with_delay(Duration::from_millis(1)));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+        assert_eq!(serial.write(b"ABCDEFG").unwrap(), 7);
+        assert_eq!(mock.tx(), b"ABCDEFG");
+        assert_eq!(mock.events().len(), 3);
+
+        // a port that takes nothing is an error once retries run out
+        mock.set_max_write_len(Some(0));
+        assert!(matches!(serial.write(b"X"), Err(BitcoreError::Io(_))));
+    }
+
+    #[test]
+    fn test_port_descriptor() {
+        use bitcore::{PortDescriptor, PortKind};
+        use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+
+        let usb = PortDescriptor::from(SerialPortInfo {
+            port_name: "/dev/ttyUSB0".to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x0403,
+                pid: 0x6001,
+                serial_number: Some("A50285BI".to_string()),
+                manufacturer: Some("FTDI".to_string()),
+                product: Some("FT232R USB UART".to_string()),
+            }),
+        });
+        assert_eq!(usb.name, "/dev/ttyUSB0");
+        assert_eq!(usb.kind, PortKind::Usb);
+        assert_eq!(usb.manufacturer.as_deref(), Some("FTDI"));
+        assert_eq!(usb.product.as_deref(), Some("FT232R USB UART"));
+        assert_eq!(usb.serial_number.as_deref(), Some("A50285BI"));
+        assert_eq!((usb.vid, usb.pid), (Some(0x0403), Some(0x6001)));
+
+        let pci = PortDescriptor::from(SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: SerialPortType::PciPort,
+        });
+        assert_eq!(pci.kind, PortKind::Pci);
+        assert_eq!((pci.manufacturer, pci.vid), (None, None));
+
+        // listing itself must not fail just because there is nothing to describe
+        assert!(Serial::list_ports_described().is_ok());
+    }
+
+    #[test]
+    fn test_line_reads_are_chunked() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        mock.push_rx(b"first\r\nsecond\nthird\nrest");
+        assert_eq!(serial.read_line().unwrap(), "first");
+        assert_eq!(serial.read_line().unwrap(), "second");
+        // what a line read took past its newline is there for any later read
+        assert_eq!(serial.bytes_available().unwrap(), 10);
+        assert_eq!(serial.read_until(b'\n', true).unwrap(), b"third\n");
+        let mut buffer = [0u8; 8];
+        assert_eq!(serial.read(&mut buffer).unwrap(), 4);
+        assert_eq!(&buffer[..4], b"rest");
+
+        // one port read served all of it, and no byte was counted twice
+        let stats = serial.stats();
+        assert_eq!(stats.reads, 1);
+        assert_eq!(stats.bytes_read, 24);
+
+        // clearing input also drops the held-back bytes
+        mock.push_rx(b"a\nb\n");
+        assert_eq!(serial.read_line().unwrap(), "a");
+        serial.discard_input().unwrap();
+        assert_eq!(serial.bytes_available().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_spawn_reader() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let reader = serial.spawn_reader(move |data| tx.send(data.to_vec()).unwrap());
+        assert!(reader.is_running());
+
+        mock.push_rx(b"hello");
+        let received = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(received, b"hello");
+
+        // stopping joins the thread, which drops the callback (and its sender)
+        reader.stop();
+        mock.push_rx(b"late");
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        assert!(matches!(
+            rx.try_recv(),
+            Err(std::sync::mpsc::TryRecvError::Disconnected)
+        ));
+
+        // the unread bytes are still there for the caller
+        let mut buffer = [0u8; 8];
+        assert_eq!(serial.read(&mut buffer).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_reader_channel() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        let channel = serial.reader_channel(2);
+
+        channel.sender().send(b"ping".to_vec()).unwrap();
+        mock.push_rx(b"pong");
+        let received = channel.receiver().recv_timeout(Duration::from_secs(1));
+        assert_eq!(received.unwrap().unwrap(), b"pong");
+        assert_eq!(mock.tx(), b"ping");
+
+        // a full channel stops the reader; the rest waits on the port
+        for chunk in [b"one", b"two", b"333", b"444"] {
+            mock.push_rx(chunk);
+            std::thread::sleep(Duration::from_millis(150));
+        }
+        assert_eq!(serial.bytes_available().unwrap(), 3);
+        let mut received = Vec::new();
+        while received.len() < 12 {
+            let chunk = channel.receiver().recv_timeout(Duration::from_secs(1));
+            received.extend(chunk.unwrap().unwrap());
+        }
+        assert_eq!(received, b"onetwo333444");
+
+        // write errors are reported on the receiver
+        mock.fail_next_writes(std::io::ErrorKind::PermissionDenied, 1);
+        channel.sender().send(b"lost".to_vec()).unwrap();
+        let error = channel.receiver().recv_timeout(Duration::from_secs(1));
+        assert!(error.unwrap().is_err());
+
+        // dropping the channel stops both threads, even with data waiting
+        mock.push_rx(b"more");
+        std::thread::sleep(Duration::from_millis(100));
+        let started = Instant::now();
+        drop(channel);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_write_fmt() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default().clear_before_write(true);
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        let value = 42;
+        write!(serial, "SET {}\r\n", value).unwrap();
+        write!(serial, "GET\r\n").unwrap();
+        assert_eq!(mock.take_tx(), b"SET 42\r\nGET\r\n");
+
+        // through io::Write the formatted text is still one transfer, even
+        // when the port takes a few bytes per write
+        mock.set_max_write_len(Some(3));
+        let mut writer: Box<dyn std::io::Write> = Box::new(serial.clone());
+        write!(writer, "SET {}\r\n", value).unwrap();
+        writer.write_all(b"GET\r\n").unwrap();
+        assert_eq!(mock.take_tx(), b"SET 42\r\nGET\r\n");
+        let clears = mock
+            .events()
+            .iter()
+            .filter(|event| matches!(event, MockEvent::Clear(_)))
+            .count();
+        assert_eq!(clears, 4);
+    }
+
+    #[test]
+    fn test_baud_rate_readback() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        mock.set_supported_baud_rates(Some(vec![9600, 115200, 230400, 921600]));
+
+        // close enough to a supported rate: accepted
+        serial.set_baud_rate(115000).unwrap();
+        assert_eq!(serial.baud_rate(), 115000);
+
+        // rounded too far: rejected, and the previous rate is back
+        let err = serial.set_baud_rate(250000).unwrap_err();
+        match err {
+            BitcoreError::InvalidParameter { param, reason } => {
+                assert_eq!(param, "baud_rate");
+                assert_eq!(reason, "driver selected 230400 instead of 250000");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(serial.baud_rate(), 115000);
+        assert_eq!(mock.baud_rate().unwrap(), 115200);
+    }
+
+    #[test]
+    fn test_oversized_frame_resyncs() {
+        let serial = Serial::loopback();
+
+        // the rest of an oversized frame is skipped, not read as the next one
+        serial.write_frame(&[0xaa; 32]).unwrap();
+        serial.write_frame(b"ok").unwrap();
+        assert!(matches!(
+            serial.read_frame(8),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+        assert_eq!(serial.read_frame(8).unwrap(), b"ok");
+
+        serial.write_slip(&[0x55; 32]).unwrap();
+        serial.write_slip(b"ok").unwrap();
+        assert!(matches!(
+            serial.read_slip(8),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+        assert_eq!(serial.read_slip(8).unwrap(), b"ok");
+    }
+
+    #[test]
+    fn test_exchange_keeps_held_back_input() {
+        let mock = MockSerial::new();
+        let config = SerialConfig::default()
+            .clear_input_before_exchange(false)
+            .timeout(Duration::from_millis(50));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // a line read held back "B"; the reply continues from there, in order
+        mock.push_rx(b"a\nB");
+        assert_eq!(serial.read_line().unwrap(), "a");
+        mock.push_rx(b"C\n");
+        assert_eq!(serial.transaction(b"q", b'\n').unwrap(), b"BC\n");
+        let mut buffer = [0u8; 4];
+        assert!(matches!(
+            serial.read(&mut buffer),
+            Err(BitcoreError::Timeout { .. })
+        ));
+
+        mock.push_rx(b"x\nyz");
+        assert_eq!(serial.read_line().unwrap(), "x");
+        mock.push_rx(b"w");
+        let mut response = [0u8; 3];
+        serial.write_read_exact(b"q", &mut response).unwrap();
+        assert_eq!(&response, b"yzw");
+        assert_eq!(mock.tx(), b"qq");
+    }
+
+    #[test]
+    fn test_every_constructor_validates() {
+        let config = SerialConfig::new(0);
+        assert!(matches!(
+            Serial::from_port(Box::new(MockSerial::new()), &config),
+            Err(BitcoreError::InvalidParameter { .. })
+        ));
+
+        // helpers taking an unvalidated config do not divide by the zero rate
+        let config = config.adaptive_timeout(true);
+        assert_eq!(config.effective_timeout(100), config.timeout);
+        assert_eq!(bitcore::modbus::frame_silence(&config), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_last_clone_closes_once() {
+        for _ in 0..20 {
+            let mock = MockSerial::new();
+            let serial =
+                Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+
+            // the last two clones drop at the same time; the port is still
+            // flushed and closed, and only once
+            let clones = [serial.clone(), serial];
+            let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+            let threads: Vec<_> = clones
+                .into_iter()
+                .map(|clone| {
+                    let barrier = std::sync::Arc::clone(&barrier);
+                    std::thread::spawn(move || {
+                        barrier.wait();
+                        drop(clone);
+                    })
+                })
+                .collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            let flushes = mock
+                .events()
+                .iter()
+                .filter(|event| matches!(event, MockEvent::Flush))
+                .count();
+            assert_eq!(flushes, 1);
+        }
+    }
+
+    #[test]
+    fn test_failed_split_keeps_connection() {
+        let transport = EchoTransport {
+            queue: Default::default(),
+            timeout: Duration::ZERO,
+        };
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_transport(Box::new(transport), &config).unwrap();
+        let other = serial.clone();
+
+        // the transport cannot be cloned; the connection stays with `other`
+        assert!(serial.split().is_err());
+        assert!(other.is_connected());
+        other.write(b"still here\n").unwrap();
+        assert_eq!(other.read_line().unwrap(), "still here");
+    }
+
+    #[test]
+    fn test_concurrent_spaced_writes() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        let gap = Duration::from_millis(100);
+
+        // two writers racing for the same gap must still be spaced apart
+        let start = Instant::now();
+        serial.write(b"0").unwrap();
+        std::thread::scope(|scope| {
+            for data in [b"1", b"2"] {
+                let serial = &serial;
+                scope.spawn(move || serial.write_spaced(data, gap).unwrap());
+            }
+        });
+        assert!(start.elapsed() >= 2 * gap);
+        assert_eq!(mock.tx().len(), 3);
+    }
+
+    #[test]
+    fn test_spaced_write_waits_outside_the_lock() {
+        let mock = MockSerial::new();
+        let serial = Serial::from_port(Box::new(mock.clone()), &SerialConfig::default()).unwrap();
+        let gap = Duration::from_millis(300);
+
+        serial.write(b"0").unwrap();
+        mock.push_rx(b"in");
+        std::thread::scope(|scope| {
+            let writer = scope.spawn(|| serial.write_spaced(b"1", gap).unwrap());
+            std::thread::sleep(Duration::from_millis(50));
+
+            // a read during the gap is not held up by the waiting writer
+            let start = Instant::now();
+            let mut buffer = [0u8; 8];
+            assert_eq!(serial.read(&mut buffer).unwrap(), 2);
+            assert!(start.elapsed() < Duration::from_millis(100));
+            writer.join().unwrap();
+        });
+
+        // a closed port fails straight away instead of waiting out the gap
+        serial.disconnect().unwrap();
+        let start = Instant::now();
+        assert!(matches!(
+            serial.write_spaced(b"2", gap),
+            Err(BitcoreError::NotConnected)
+        ));
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_read_uninit_takes_held_back_input() {
+        init_tracing();
+
+        use std::mem::MaybeUninit;
+
+        let mock = MockSerial::new();
+        let config = SerialConfig::new(115200).timeout(Duration::from_millis(100));
+        let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+        // read_line keeps what followed the newline for the next read
+        mock.push_rx(b"first\nrest");
+        assert_eq!(serial.read_line().unwrap(), "first");
+
+        let mut buffer = [MaybeUninit::<u8>::uninit(); 16];
+        let read = serial.read_uninit(&mut buffer).unwrap();
+        let bytes: Vec<u8> = buffer[..read]
+            .iter()
+            .map(|slot| unsafe { slot.assume_init() })
+            .collect();
+        assert_eq!(bytes, b"rest");
+    }
+
+    #[test]
+    fn test_length_prefixed_adaptive_timeout() {
+        use bitcore::framing::LengthPrefix;
+
+        // a 960-byte frame whose payload shows up 300ms after its header: at
+        // 9600 baud the wire time (1s) covers that, at 115200 (~83ms) it does not
+        for (baud_rate, arrives) in [(9600, true), (115200, false)] {
+            let mock = MockSerial::new();
+            let config = SerialConfig::new(baud_rate)
+                .timeout(Duration::from_millis(50))
+                .adaptive_timeout(true);
+            let serial = Serial::from_port(Box::new(mock.clone()), &config).unwrap();
+
+            mock.push_rx(&960u16.to_be_bytes());
+            let feeder = {
+                let mock = mock.clone();
+                std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(300));
+                    mock.push_rx(&[0x55; 960]);
+                })
+            };
+            let result = serial.read_length_prefixed(LengthPrefix::U16Be, 1024);
+            feeder.join().unwrap();
+
+            if arrives {
+                assert_eq!(result.unwrap(), vec![0x55; 960]);
+            } else {
+                assert!(matches!(result, Err(BitcoreError::Timeout { .. })));
+            }
+        }
+    }
 }